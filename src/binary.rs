@@ -0,0 +1,571 @@
+// Binary frames: COBS or SLIP packets holding fields described by a layout.
+
+use crate::checksum::Checksum;
+use crate::clock::Clock;
+use crate::parser::Record;
+use crate::settings::Settings;
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Framing
+{
+    Cobs,
+    Slip,
+}
+
+impl Framing
+{
+    fn delimiter(&self) -> u8
+    {
+        match self {
+            Framing::Cobs => 0x00,
+            Framing::Slip => SLIP_END,
+        }
+    }
+
+    fn decode(&self, packet: &[u8]) -> Option<Vec<u8>>
+    {
+        match self {
+            Framing::Cobs => cobs_decode(packet),
+            Framing::Slip => slip_decode(packet),
+        }
+    }
+
+    // Frames the data as one packet, ending with the delimiter.
+    pub fn encode(&self, data: &[u8]) -> Vec<u8>
+    {
+        let mut packet = match self {
+            Framing::Cobs => cobs_encode(data),
+            Framing::Slip => slip_encode(data),
+        };
+        packet.push(self.delimiter());
+        packet
+    }
+}
+
+pub fn cobs_encode(data: &[u8]) -> Vec<u8>
+{
+    let mut packet = vec![0];
+    let mut code_index = 0;
+
+    for &byte in data
+    {
+        if byte != 0
+        {
+            packet.push(byte);
+        }
+
+        if byte == 0 || packet.len() - code_index == 0xFF
+        {
+            packet[code_index] = (packet.len() - code_index) as u8;
+            code_index = packet.len();
+            packet.push(0);
+        }
+    }
+    packet[code_index] = (packet.len() - code_index) as u8;
+    packet
+}
+
+pub fn cobs_decode(packet: &[u8]) -> Option<Vec<u8>>
+{
+    let mut data = Vec::with_capacity(packet.len());
+    let mut i = 0;
+
+    while i < packet.len()
+    {
+        let code = packet[i] as usize;
+        if code == 0 || i + code > packet.len()
+        {
+            return None;
+        }
+
+        data.extend_from_slice(&packet[i + 1..i + code]);
+        i += code;
+
+        if code < 0xFF && i < packet.len()
+        {
+            data.push(0);
+        }
+    }
+    Some(data)
+}
+
+pub fn slip_encode(data: &[u8]) -> Vec<u8>
+{
+    let mut packet = Vec::with_capacity(data.len());
+
+    for &byte in data
+    {
+        match byte {
+            SLIP_END => packet.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => packet.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+            _ => packet.push(byte),
+        }
+    }
+    packet
+}
+
+pub fn slip_decode(packet: &[u8]) -> Option<Vec<u8>>
+{
+    let mut data = Vec::with_capacity(packet.len());
+    let mut bytes = packet.iter();
+
+    while let Some(&byte) = bytes.next()
+    {
+        if byte != SLIP_ESC
+        {
+            data.push(byte);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(&SLIP_ESC_END) => data.push(SLIP_END),
+            Some(&SLIP_ESC_ESC) => data.push(SLIP_ESC),
+            _ => return None,
+        }
+    }
+    Some(data)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldType
+{
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+}
+
+impl FieldType
+{
+    pub const ALL: [FieldType; 10] = [FieldType::U8, FieldType::I8, FieldType::U16, FieldType::I16, FieldType::U32, FieldType::I32, FieldType::U64, FieldType::I64, FieldType::F32, FieldType::F64];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            FieldType::U8 => "u8",
+            FieldType::I8 => "i8",
+            FieldType::U16 => "u16",
+            FieldType::I16 => "i16",
+            FieldType::U32 => "u32",
+            FieldType::I32 => "i32",
+            FieldType::U64 => "u64",
+            FieldType::I64 => "i64",
+            FieldType::F32 => "f32",
+            FieldType::F64 => "f64",
+        }
+    }
+
+    pub fn size(&self) -> usize
+    {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+        }
+    }
+
+    // Writes the value rounded and saturated to the type.
+    fn encode(&self, value: f64, big_endian: bool) -> Vec<u8>
+    {
+        let mut bytes = match self {
+            FieldType::U8 => (value.round() as u8).to_le_bytes().to_vec(),
+            FieldType::I8 => (value.round() as i8).to_le_bytes().to_vec(),
+            FieldType::U16 => (value.round() as u16).to_le_bytes().to_vec(),
+            FieldType::I16 => (value.round() as i16).to_le_bytes().to_vec(),
+            FieldType::U32 => (value.round() as u32).to_le_bytes().to_vec(),
+            FieldType::I32 => (value.round() as i32).to_le_bytes().to_vec(),
+            FieldType::U64 => (value.round() as u64).to_le_bytes().to_vec(),
+            FieldType::I64 => (value.round() as i64).to_le_bytes().to_vec(),
+            FieldType::F32 => (value as f32).to_le_bytes().to_vec(),
+            FieldType::F64 => value.to_le_bytes().to_vec(),
+        };
+        if big_endian
+        {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    fn decode(&self, bytes: &[u8], big_endian: bool) -> f64
+    {
+        let mut buf = [0u8; 8];
+        let size = self.size();
+        buf[..size].copy_from_slice(&bytes[..size]);
+        if big_endian
+        {
+            buf[..size].reverse();
+        }
+
+        match self {
+            FieldType::U8 => buf[0] as f64,
+            FieldType::I8 => buf[0] as i8 as f64,
+            FieldType::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            FieldType::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            FieldType::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            FieldType::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            FieldType::U64 => u64::from_le_bytes(buf) as f64,
+            FieldType::I64 => i64::from_le_bytes(buf) as f64,
+            FieldType::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            FieldType::F64 => f64::from_le_bytes(buf),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field
+{
+    pub kind: FieldType,
+    pub count: usize,
+    pub name: String,
+}
+
+// Fields of a payload in order, written like `u32 timestamp, i16x8, f32x2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout
+{
+    pub fields: Vec<Field>,
+    pub big_endian: bool,
+}
+
+impl Layout
+{
+    pub fn parse(text: &str, big_endian: bool) -> Result<Layout, String>
+    {
+        let mut fields = Vec::new();
+
+        for part in text.split([',', ';']).map(str::trim).filter(|part| !part.is_empty())
+        {
+            let mut words = part.split_whitespace();
+            let kind = words.next().unwrap_or("");
+            let name = words.collect::<Vec<_>>().join(" ");
+
+            let (kind, count) = match kind.split_once(['x', '×', '*']) {
+                Some((kind, count)) => (kind, count.parse::<usize>().map_err(|_| "Incorrect layout: ".to_string() + part)?),
+                None => (kind, 1),
+            };
+
+            let Some(kind) = FieldType::ALL.into_iter().find(|field| field.name() == kind) else
+            {
+                return Err("Incorrect layout: ".to_string() + part);
+            };
+
+            fields.push(Field { kind, count, name });
+        }
+
+        if fields.is_empty()
+        {
+            return Err("Incorrect layout: no fields".to_string());
+        }
+        Ok(Layout { fields, big_endian })
+    }
+
+    pub fn size(&self) -> usize
+    {
+        self.fields.iter().map(|field| field.kind.size() * field.count).sum()
+    }
+
+    pub fn len(&self) -> usize
+    {
+        self.fields.iter().map(|field| field.count).sum()
+    }
+
+    // Channel names of named fields, as (index, name).
+    pub fn names(&self) -> Vec<(usize, String)>
+    {
+        let mut names = Vec::new();
+        let mut index = 0;
+
+        for field in &self.fields
+        {
+            for i in 0..field.count
+            {
+                if !field.name.is_empty()
+                {
+                    names.push((index, if field.count == 1 { field.name.to_string() } else { field.name.to_string() + "." + &i.to_string() }));
+                }
+                index += 1;
+            }
+        }
+        names
+    }
+
+    pub fn decode(&self, payload: &[u8]) -> Option<Vec<f64>>
+    {
+        if payload.len() != self.size()
+        {
+            return None;
+        }
+
+        let mut values = Vec::with_capacity(self.len());
+        let mut offset = 0;
+
+        for field in &self.fields
+        {
+            for _ in 0..field.count
+            {
+                values.push(field.kind.decode(&payload[offset..], self.big_endian));
+                offset += field.kind.size();
+            }
+        }
+        Some(values)
+    }
+
+    // Writes one value per field, missing values as 0.
+    pub fn encode(&self, values: &[f64]) -> Vec<u8>
+    {
+        let mut payload = Vec::with_capacity(self.size());
+        let mut values = values.iter();
+
+        for field in &self.fields
+        {
+            for _ in 0..field.count
+            {
+                payload.extend(field.kind.encode(*values.next().unwrap_or(&0.0), self.big_endian));
+            }
+        }
+        payload
+    }
+}
+
+pub struct Decoder
+{
+    framing: Framing,
+    layout: Layout,
+    checksum: Checksum,
+    clock: Clock,
+    // Field names by index, empty for fields without a name.
+    names: Vec<String>,
+    buffer: Vec<u8>,
+    corrupt: u64,
+    // Bytes before the first delimiter belong to a packet that started before the port was opened.
+    synced: bool,
+}
+
+impl Decoder
+{
+    pub fn new(framing: Framing, settings: &Settings) -> Result<Decoder, String>
+    {
+        let layout = Layout::parse(&settings.layout, settings.big_endian)?;
+
+        let mut names = vec!["".to_string(); layout.len()];
+        for (index, name) in layout.names()
+        {
+            names[index] = name;
+        }
+
+        Ok(Decoder
+        {
+            framing,
+            layout,
+            checksum: settings.checksum,
+            clock: Clock::new(settings),
+            names,
+            buffer: Vec::new(),
+            corrupt: 0,
+            synced: false,
+        })
+    }
+
+    // Takes the next byte as the start of a packet.
+    pub fn sync(&mut self)
+    {
+        self.synced = true;
+    }
+
+    // Drops the packet in progress and skips the next incomplete one, after the port was reopened.
+    pub fn resync(&mut self)
+    {
+        self.synced = false;
+        self.buffer.clear();
+        self.clock.restart();
+    }
+
+    pub fn delimiter(&self) -> u8
+    {
+        self.framing.delimiter()
+    }
+
+    // Channel names of named fields except the time field, as (index, name).
+    pub fn names(&self) -> Vec<(usize, String)>
+    {
+        self.layout.names().into_iter().filter(|(index, name)| !self.clock.is_field(name) && !self.clock.is_field(&index.to_string())).collect()
+    }
+
+    // Returns every complete packet as received, with its frame or None if it was dropped.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<(Vec<u8>, Option<Record>)>
+    {
+        let mut frames = Vec::new();
+
+        for &byte in bytes
+        {
+            if byte != self.framing.delimiter()
+            {
+                self.buffer.push(byte);
+                continue;
+            }
+
+            let packet = std::mem::take(&mut self.buffer);
+            if !self.synced
+            {
+                self.synced = true;
+                continue;
+            }
+
+            if packet.is_empty()
+            {
+                continue;
+            }
+
+            let Some(payload) = self.framing.decode(&packet) else
+            {
+                frames.push((packet, None));
+                continue;
+            };
+
+            let Some(payload) = self.checksum.check_binary(&payload, self.layout.big_endian) else
+            {
+                self.corrupt += 1;
+                frames.push((packet, None));
+                continue;
+            };
+
+            let Some(values) = self.layout.decode(payload) else
+            {
+                frames.push((packet, None));
+                continue;
+            };
+
+            let mut record = Record::new(values.into_iter().map(Some).collect());
+            self.clock.take(&mut record, &self.names);
+            frames.push((packet, Some(record)));
+        }
+        frames
+    }
+
+    // Number of packets dropped for a wrong checksum since the last call.
+    pub fn take_corrupt(&mut self) -> u64
+    {
+        std::mem::take(&mut self.corrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::clock::TimeUnit;
+
+    fn records(frames: Vec<(Vec<u8>, Option<Record>)>) -> Vec<Option<Record>>
+    {
+        frames.into_iter().map(|(_, record)| record).collect()
+    }
+
+    #[test]
+    fn cobs()
+    {
+        assert_eq!(cobs_decode(&[0x03, 0x11, 0x22, 0x02, 0x33]), Some(vec![0x11, 0x22, 0x00, 0x33]));
+        assert_eq!(cobs_decode(&[0x01, 0x01]), Some(vec![0x00]));
+        assert_eq!(cobs_decode(&[0x01]), Some(vec![]));
+        assert_eq!(cobs_decode(&[0x05, 0x11]), None);
+        assert_eq!(cobs_decode(&[0x02, 0x11, 0x00]), None);
+
+        // A full block of 254 bytes is not followed by a zero.
+        let mut packet = vec![0xFF];
+        packet.extend([1; 254]);
+        packet.extend([0x02, 0x07]);
+        let mut data = vec![1; 254];
+        data.push(0x07);
+        assert_eq!(cobs_decode(&packet), Some(data));
+    }
+
+    #[test]
+    fn slip()
+    {
+        assert_eq!(slip_decode(&[1, SLIP_ESC, SLIP_ESC_END, 2, SLIP_ESC, SLIP_ESC_ESC, 3]), Some(vec![1, SLIP_END, 2, SLIP_ESC, 3]));
+        assert_eq!(slip_decode(&[1, SLIP_ESC, 7]), None);
+        assert_eq!(slip_decode(&[1, SLIP_ESC]), None);
+    }
+
+    #[test]
+    fn layout_parse()
+    {
+        let layout = Layout::parse("u32 timestamp, i16x2 accel; f32×1, u8*3", false).unwrap();
+        assert_eq!(layout.fields.iter().map(|field| (field.kind, field.count)).collect::<Vec<_>>(), [(FieldType::U32, 1), (FieldType::I16, 2), (FieldType::F32, 1), (FieldType::U8, 3)]);
+        assert_eq!(layout.size(), 4 + 4 + 4 + 3);
+        assert_eq!(layout.len(), 7);
+        assert_eq!(layout.names(), [(0, "timestamp".to_string()), (1, "accel.0".to_string()), (2, "accel.1".to_string())]);
+
+        assert!(Layout::parse("", false).is_err());
+        assert!(Layout::parse("u24", false).is_err());
+        assert!(Layout::parse("i16xa", false).is_err());
+    }
+
+    #[test]
+    fn layout_decode()
+    {
+        let mut payload = vec![200, 0x9C];
+        payload.extend(60_000u16.to_le_bytes());
+        payload.extend((-30_000i16).to_le_bytes());
+        payload.extend(1.5f32.to_le_bytes());
+        payload.extend((-0.125f64).to_le_bytes());
+        payload.extend(4_000_000_000u32.to_le_bytes());
+        payload.extend((-1_000_000_000_000_000i64).to_le_bytes());
+
+        let layout = Layout::parse("u8, i8, u16, i16, f32, f64, u32, i64", false).unwrap();
+        assert_eq!(layout.decode(&payload), Some(vec![200.0, -100.0, 60_000.0, -30_000.0, 1.5, -0.125, 4e9, -1e15]));
+        assert_eq!(layout.decode(&payload[1..]), None);
+
+        assert_eq!(Layout::parse("u16, i32", true).unwrap().decode(&[0x01, 0x02, 0xFF, 0xFF, 0xFF, 0xFE]), Some(vec![258.0, -2.0]));
+        assert_eq!(Layout::parse("u16", false).unwrap().decode(&[0x01, 0x02]), Some(vec![513.0]));
+    }
+
+    #[test]
+    fn decoder()
+    {
+        let settings = Settings { layout: "u8, i16".to_string(), ..Settings::default() };
+        let mut decoder = Decoder::new(Framing::Slip, &settings).unwrap();
+
+        // The bytes before the first delimiter are the end of a packet sent before the port was opened.
+        let bytes = [0x12, SLIP_END, 1, 0xFE, 0xFF, SLIP_END, SLIP_END, 2, 3, SLIP_END, 1, SLIP_ESC, SLIP_ESC_END, 0, SLIP_END];
+        let frames: Vec<(Vec<u8>, Option<Record>)> = decoder.push(&bytes[..3]).into_iter().chain(decoder.push(&bytes[3..])).collect();
+        assert_eq!(frames, [
+            (vec![1, 0xFE, 0xFF], Some(Record::new(vec![Some(1.0), Some(-2.0)]))),
+            (vec![2, 3], None),
+            (vec![1, SLIP_ESC, SLIP_ESC_END, 0], Some(Record::new(vec![Some(1.0), Some(192.0)]))),
+        ]);
+
+        // The CRC-8 of 1, 2 is 0x1B, a packet with a wrong one is counted as corrupt.
+        let settings = Settings { layout: "u8x2".to_string(), checksum: Checksum::Crc8, ..Settings::default() };
+        let mut decoder = Decoder::new(Framing::Cobs, &settings).unwrap();
+        assert_eq!(records(decoder.push(&[0, 4, 1, 2, 0x1B, 0, 4, 1, 2, 0x1C, 0])), [Some(Record::new(vec![Some(1.0), Some(2.0)])), None]);
+        assert_eq!(decoder.take_corrupt(), 1);
+
+        assert!(Decoder::new(Framing::Cobs, &Settings { layout: "".to_string(), ..Settings::default() }).is_err());
+    }
+
+    #[test]
+    fn time_field()
+    {
+        let settings = Settings { layout: "u16 t, i8 x".to_string(), time_field: "t".to_string(), time_unit: TimeUnit::Ms, time_wrap: 16, ..Settings::default() };
+        let mut decoder = Decoder::new(Framing::Cobs, &settings).unwrap();
+        assert_eq!(decoder.names(), [(1, "x".to_string())]);
+
+        // 65535 ms, then 9 ms after the counter wrapped.
+        assert_eq!(records(decoder.push(&[0, 4, 0xFF, 0xFF, 5, 0, 2, 9, 2, 0xFF, 0])), [
+            Some(Record { timestamp: Some(0.0), values: vec![None, Some(5.0)] }),
+            Some(Record { timestamp: Some(0.01), values: vec![None, Some(-1.0)] }),
+        ]);
+    }
+}
@@ -0,0 +1,217 @@
+// Checksums a frame can carry: text lines end with `*` and the value in hex, binary payloads end with its bytes.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Checksum
+{
+    None,
+    // CRC-8/SMBUS, polynomial 0x07.
+    Crc8,
+    // CRC-16/CCITT-FALSE, polynomial 0x1021, initial value 0xFFFF.
+    Crc16,
+    // CRC-32 as used by Ethernet and zlib.
+    Crc32,
+    // XOR of all bytes, as in NMEA sentences.
+    Xor,
+}
+
+impl Checksum
+{
+    pub const ALL: [Checksum; 5] = [Checksum::None, Checksum::Crc8, Checksum::Crc16, Checksum::Crc32, Checksum::Xor];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Checksum::None => "none",
+            Checksum::Crc8 => "crc8",
+            Checksum::Crc16 => "crc16",
+            Checksum::Crc32 => "crc32",
+            Checksum::Xor => "xor",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Checksum>
+    {
+        Checksum::ALL.into_iter().find(|checksum| checksum.name() == name)
+    }
+
+    // Size of the checksum in bytes.
+    pub fn size(&self) -> usize
+    {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc8 | Checksum::Xor => 1,
+            Checksum::Crc16 => 2,
+            Checksum::Crc32 => 4,
+        }
+    }
+
+    pub fn compute(&self, data: &[u8]) -> u32
+    {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc8 => crc8(data) as u32,
+            Checksum::Crc16 => crc16(data) as u32,
+            Checksum::Crc32 => crc32(data),
+            Checksum::Xor => data.iter().fold(0, |sum, byte| sum ^ byte) as u32,
+        }
+    }
+
+    // Checks a text line like `1.2,3.4*5A` and returns it without the checksum.
+    // A leading `$` is not part of the checksummed text, as in NMEA sentences.
+    pub fn check_text<'a>(&self, line: &'a str) -> Option<&'a str>
+    {
+        if *self == Checksum::None
+        {
+            return Some(line);
+        }
+
+        let (data, sum) = line.rsplit_once('*')?;
+        let sum = u32::from_str_radix(sum.trim(), 16).ok()?;
+        let data = data.strip_prefix('$').unwrap_or(data);
+
+        if self.compute(data.as_bytes()) != sum
+        {
+            return None;
+        }
+        Some(data)
+    }
+
+    // Appends the checksum to a text line as `*` and hex digits.
+    pub fn append_text(&self, line: &str) -> String
+    {
+        if *self == Checksum::None
+        {
+            return line.to_string();
+        }
+        format!("{}*{:0width$X}", line, self.compute(line.as_bytes()), width = self.size() * 2)
+    }
+
+    // Appends the checksum to a binary payload.
+    pub fn append_binary(&self, payload: &mut Vec<u8>, big_endian: bool)
+    {
+        let sum = self.compute(payload).to_le_bytes();
+        let mut sum = sum[..self.size()].to_vec();
+        if big_endian
+        {
+            sum.reverse();
+        }
+        payload.extend(sum);
+    }
+
+    // Checks the checksum at the end of a binary payload and returns the payload without it.
+    pub fn check_binary<'a>(&self, payload: &'a [u8], big_endian: bool) -> Option<&'a [u8]>
+    {
+        if payload.len() < self.size()
+        {
+            return None;
+        }
+
+        let (data, sum) = payload.split_at(payload.len() - self.size());
+        let sum = if big_endian
+        {
+            sum.iter().fold(0u32, |value, &byte| (value << 8) | byte as u32)
+        }
+        else
+        {
+            sum.iter().rev().fold(0u32, |value, &byte| (value << 8) | byte as u32)
+        };
+
+        if self.compute(data) != sum
+        {
+            return None;
+        }
+        Some(data)
+    }
+}
+
+fn crc8(data: &[u8]) -> u8
+{
+    let mut crc = 0u8;
+    for &byte in data
+    {
+        crc ^= byte;
+        for _ in 0..8
+        {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16
+{
+    let mut crc = 0xFFFFu16;
+    for &byte in data
+    {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8
+        {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc32(data: &[u8]) -> u32
+{
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data
+    {
+        crc ^= byte as u32;
+        for _ in 0..8
+        {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    // Check values of the catalogue of CRC parameters, computed over "123456789".
+    #[test]
+    fn check_values()
+    {
+        let data = b"123456789";
+        assert_eq!(Checksum::Crc8.compute(data), 0xF4);
+        assert_eq!(Checksum::Crc16.compute(data), 0x29B1);
+        assert_eq!(Checksum::Crc32.compute(data), 0xCBF4_3926);
+        assert_eq!(Checksum::Xor.compute(data), 0x31);
+        assert_eq!(Checksum::None.compute(data), 0);
+    }
+
+    #[test]
+    fn text()
+    {
+        assert_eq!(Checksum::None.check_text("1.2,3.4"), Some("1.2,3.4"));
+        assert_eq!(Checksum::Xor.check_text("1.2,3.4*28"), Some("1.2,3.4"));
+        assert_eq!(Checksum::Xor.check_text("1.2,3.4*28 "), Some("1.2,3.4"));
+        assert_eq!(Checksum::Xor.check_text("$1.2,3.4*28"), Some("1.2,3.4"));
+        assert_eq!(Checksum::Xor.check_text("1.2,3.4*29"), None);
+        assert_eq!(Checksum::Xor.check_text("1.2,3.4*zz"), None);
+        assert_eq!(Checksum::Crc8.check_text("1.2,3.4"), None);
+        assert_eq!(Checksum::Crc16.check_text("123456789*29b1"), Some("123456789"));
+        assert_eq!(Checksum::Crc32.check_text("123456789*CBF43926"), Some("123456789"));
+    }
+
+    #[test]
+    fn binary()
+    {
+        let data = b"123456789";
+        for (checksum, little, big) in [
+            (Checksum::Crc8, vec![0xF4], vec![0xF4]),
+            (Checksum::Crc16, vec![0xB1, 0x29], vec![0x29, 0xB1]),
+            (Checksum::Crc32, vec![0x26, 0x39, 0xF4, 0xCB], vec![0xCB, 0xF4, 0x39, 0x26]),
+        ]
+        {
+            assert_eq!(checksum.check_binary(&[&data[..], &little].concat(), false), Some(&data[..]));
+            assert_eq!(checksum.check_binary(&[&data[..], &big].concat(), true), Some(&data[..]));
+            assert_eq!(checksum.check_binary(&[&data[..], &big].concat(), false), if checksum.size() == 1 { Some(&data[..]) } else { None });
+            assert_eq!(checksum.check_binary(&little[1..], false), None);
+        }
+        assert_eq!(Checksum::None.check_binary(data, false), Some(&data[..]));
+    }
+}
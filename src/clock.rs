@@ -0,0 +1,178 @@
+// Device time taken from a field of the frame.
+
+use crate::parser::Record;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeUnit
+{
+    S,
+    Ms,
+    Us,
+}
+
+impl TimeUnit
+{
+    pub const ALL: [TimeUnit; 3] = [TimeUnit::S, TimeUnit::Ms, TimeUnit::Us];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            TimeUnit::S => "s",
+            TimeUnit::Ms => "ms",
+            TimeUnit::Us => "us",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TimeUnit>
+    {
+        TimeUnit::ALL.into_iter().find(|unit| unit.name() == name)
+    }
+
+    pub fn seconds(&self) -> f64
+    {
+        match self {
+            TimeUnit::S => 1.0,
+            TimeUnit::Ms => 1e-3,
+            TimeUnit::Us => 1e-6,
+        }
+    }
+}
+
+pub struct Clock
+{
+    // Index or name of the field, empty to use the host time.
+    field: String,
+    unit: TimeUnit,
+    // Width in bits of a counter that wraps around, like 32 for `millis()`, 0 if it does not wrap.
+    wrap: u32,
+    last: Option<f64>,
+    offset: f64,
+    first: Option<f64>,
+}
+
+impl Clock
+{
+    pub fn new(settings: &Settings) -> Clock
+    {
+        Clock
+        {
+            field: settings.time_field.trim().to_string(),
+            unit: settings.time_unit,
+            wrap: settings.time_wrap,
+            last: None,
+            offset: 0.0,
+            first: None,
+        }
+    }
+
+    pub fn is_field(&self, name: &str) -> bool
+    {
+        !self.field.is_empty() && self.field == name
+    }
+
+    // Time in seconds since the first frame.
+    pub fn seconds(&mut self, raw: f64) -> f64
+    {
+        if self.wrap > 0
+        {
+            let modulus = 2f64.powi(self.wrap as i32);
+            if let Some(last) = self.last
+            {
+                if raw < last - modulus / 2.0
+                {
+                    self.offset += modulus;
+                }
+            }
+            self.last = Some(raw);
+        }
+
+        let value = raw + self.offset;
+        let first = *self.first.get_or_insert(value);
+        (value - first) * self.unit.seconds()
+    }
+
+    // Starts over, for a device that may have reset and restarted its counter.
+    pub fn restart(&mut self)
+    {
+        self.last = None;
+        self.offset = 0.0;
+        self.first = None;
+    }
+
+    // Moves the time field of the frame to its timestamp, `names` holds the channel names by index.
+    pub fn take(&mut self, record: &mut Record, names: &[String])
+    {
+        if self.field.is_empty()
+        {
+            return;
+        }
+
+        let index = match self.field.parse::<usize>() {
+            Ok(index) => Some(index),
+            Err(_) => names.iter().position(|name| *name == self.field),
+        };
+
+        if let Some(raw) = index.and_then(|index| record.values.get_mut(index)).and_then(|value| value.take())
+        {
+            record.timestamp = Some(self.seconds(raw));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn counter(unit: TimeUnit, wrap: u32) -> Clock
+    {
+        Clock::new(&Settings { time_field: "t".to_string(), time_unit: unit, time_wrap: wrap, ..Settings::default() })
+    }
+
+    #[test]
+    fn starts_at_first_frame()
+    {
+        let mut clock = counter(TimeUnit::Ms, 0);
+        assert_eq!(clock.seconds(5000.0), 0.0);
+        assert_eq!(clock.seconds(6500.0), 1.5);
+
+        let mut clock = counter(TimeUnit::Us, 0);
+        clock.seconds(10.0);
+        assert_eq!(clock.seconds(2_000_010.0), 2.0);
+    }
+
+    #[test]
+    fn wraps_around()
+    {
+        let mut clock = counter(TimeUnit::Ms, 16);
+        assert_eq!(clock.seconds(65_530.0), 0.0);
+        assert_eq!(clock.seconds(65_535.0), 0.005);
+        assert_eq!(clock.seconds(4.0), 0.010);
+        assert_eq!(clock.seconds(1004.0), 1.010);
+
+        // A small step back is not taken for a wrap.
+        let mut clock = counter(TimeUnit::S, 16);
+        clock.seconds(100.0);
+        assert_eq!(clock.seconds(90.0), -10.0);
+    }
+
+    #[test]
+    fn take()
+    {
+        let mut record = Record::new(vec![Some(1.0), Some(2000.0), Some(3.0)]);
+        let mut clock = Clock::new(&Settings { time_field: "1".to_string(), time_unit: TimeUnit::Ms, ..Settings::default() });
+        clock.take(&mut record, &[]);
+        assert_eq!(record, Record { timestamp: Some(0.0), values: vec![Some(1.0), None, Some(3.0)] });
+
+        let mut record = Record::new(vec![Some(1.0), Some(2.0)]);
+        let mut clock = counter(TimeUnit::S, 0);
+        clock.take(&mut record, &["a".to_string(), "t".to_string()]);
+        assert_eq!(record, Record { timestamp: Some(0.0), values: vec![Some(1.0), None] });
+
+        // Without a time field the frame keeps all its values.
+        let mut record = Record::new(vec![Some(1.0)]);
+        Clock::new(&Settings::default()).take(&mut record, &[]);
+        assert_eq!(record, Record::new(vec![Some(1.0)]));
+    }
+}
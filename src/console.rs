@@ -0,0 +1,216 @@
+// Commands typed to be sent to the device.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineEnding
+{
+    None,
+    Lf,
+    Cr,
+    CrLf,
+}
+
+impl LineEnding
+{
+    pub const ALL: [LineEnding; 4] = [LineEnding::None, LineEnding::Lf, LineEnding::Cr, LineEnding::CrLf];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            LineEnding::None => "none",
+            LineEnding::Lf => "lf",
+            LineEnding::Cr => "cr",
+            LineEnding::CrLf => "crlf",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LineEnding>
+    {
+        LineEnding::ALL.into_iter().find(|line_ending| line_ending.name() == name)
+    }
+
+    fn bytes(&self) -> &'static [u8]
+    {
+        match self {
+            LineEnding::None => b"",
+            LineEnding::Lf => b"\n",
+            LineEnding::Cr => b"\r",
+            LineEnding::CrLf => b"\r\n",
+        }
+    }
+}
+
+// Bytes of a command, written as text followed by the line ending, or as hex bytes like `01 A0 0xff` sent as they are.
+pub fn encode(text: &str, line_ending: LineEnding, hex: bool) -> Result<Vec<u8>, String>
+{
+    if !hex
+    {
+        let mut data = text.as_bytes().to_vec();
+        data.extend_from_slice(line_ending.bytes());
+        return Ok(data);
+    }
+
+    let mut data = Vec::new();
+    for token in text.split([' ', ',', '\t']).filter(|token| !token.is_empty())
+    {
+        let digits = token.strip_prefix("0x").or(token.strip_prefix("0X")).unwrap_or(token);
+        if digits.len() % 2 != 0 || !digits.is_ascii()
+        {
+            return Err("Incorrect hex: ".to_string() + token);
+        }
+
+        for i in (0..digits.len()).step_by(2)
+        {
+            data.push(u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| "Incorrect hex: ".to_string() + token)?);
+        }
+    }
+    Ok(data)
+}
+
+// Command saved under a name, for a button that sends it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Macro
+{
+    pub name: String,
+    pub command: String,
+}
+
+impl Macro
+{
+    // Written in the settings as `name|command`.
+    pub fn parse(value: &str) -> Option<Macro>
+    {
+        let (name, command) = value.split_once('|')?;
+        Some(Macro { name: name.to_string(), command: command.to_string() })
+    }
+
+    pub fn value(&self) -> String
+    {
+        self.name.to_string() + "|" + &self.command
+    }
+}
+
+// Text being typed and the commands sent before it, newest last.
+#[derive(Default)]
+pub struct Console
+{
+    pub text: String,
+    pub macro_name: String,
+    pub history: Vec<String>,
+    // Position in the history while going through it with the arrow keys.
+    position: Option<usize>,
+}
+
+impl Console
+{
+    pub fn push_history(&mut self, command: &str)
+    {
+        if self.history.last().map(String::as_str) != Some(command)
+        {
+            self.history.push(command.to_string());
+        }
+        self.position = None;
+    }
+
+    pub fn previous(&mut self)
+    {
+        let position = match self.position {
+            Some(position) => position.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.position = Some(position);
+        self.text = self.history[position].to_string();
+    }
+
+    pub fn next(&mut self)
+    {
+        let Some(position) = self.position else
+        {
+            return;
+        };
+
+        if position + 1 < self.history.len()
+        {
+            self.position = Some(position + 1);
+            self.text = self.history[position + 1].to_string();
+        }
+        else
+        {
+            self.position = None;
+            self.text.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn encode_text()
+    {
+        assert_eq!(encode("ab", LineEnding::None, false), Ok(b"ab".to_vec()));
+        assert_eq!(encode("ab", LineEnding::Lf, false), Ok(b"ab\n".to_vec()));
+        assert_eq!(encode("ab", LineEnding::Cr, false), Ok(b"ab\r".to_vec()));
+        assert_eq!(encode("é", LineEnding::CrLf, false), Ok("é\r\n".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn encode_hex()
+    {
+        // The line ending is not added to hex bytes.
+        assert_eq!(encode("01 A0 0xff,0XfE\t7f", LineEnding::CrLf, true), Ok(vec![0x01, 0xA0, 0xFF, 0xFE, 0x7F]));
+        assert_eq!(encode("0102  ", LineEnding::None, true), Ok(vec![0x01, 0x02]));
+        assert_eq!(encode("", LineEnding::Lf, true), Ok(Vec::new()));
+        assert!(encode("1", LineEnding::None, true).is_err());
+        assert!(encode("0x123", LineEnding::None, true).is_err());
+        assert!(encode("zz", LineEnding::None, true).is_err());
+        // Two bytes of one character are not split into digits.
+        assert!(encode("éé", LineEnding::None, true).is_err());
+        assert!(encode("0é", LineEnding::None, true).is_err());
+    }
+
+    #[test]
+    fn history()
+    {
+        let mut console = Console::default();
+        console.previous();
+        console.next();
+        assert_eq!(console.text, "");
+
+        // A command sent again right after itself is kept once.
+        for command in ["a", "b", "b", "c"]
+        {
+            console.push_history(command);
+        }
+        assert_eq!(console.history, ["a", "b", "c"]);
+
+        console.previous();
+        assert_eq!(console.text, "c");
+        console.previous();
+        console.previous();
+        assert_eq!(console.text, "a");
+        // The oldest command stays when going further back.
+        console.previous();
+        assert_eq!(console.text, "a");
+
+        console.next();
+        assert_eq!(console.text, "b");
+        console.next();
+        console.next();
+        assert_eq!(console.text, "");
+        console.next();
+        assert_eq!(console.text, "");
+
+        // Sending starts going through the history from the newest command again.
+        console.previous();
+        console.previous();
+        console.push_history("a");
+        assert_eq!(console.history, ["a", "b", "c", "a"]);
+        console.previous();
+        assert_eq!(console.text, "a");
+        console.previous();
+        assert_eq!(console.text, "c");
+    }
+}
@@ -1,7 +1,15 @@
+use eframe::egui::ecolor::Hsva;
+
+// Source and index of a channel.
+pub type ChannelKey = (usize, usize);
+
 #[derive(PartialEq)]
 pub struct DataLine
 {
     pub data: Vec<[f64; 2]>,
+    // Lowest and highest value around the line as runs of (time, lowest, highest), drawn as two lines in the plot.
+    // A run ends where a value comes without them, so the lines do not cross the values taken another way.
+    pub band: Vec<Vec<[f64; 3]>>,
     pub rgb: [f32; 3],
     pub name: String,
 }
@@ -12,38 +20,77 @@ impl DataLine
     {
         DataLine
         {
-            name: name,
+            name,
             data: xyz,
+            band: Vec::new(),
             rgb: [255.0, 0.0, 0.0],
         }
     }
+    // Picks the color from the name, so a named channel looks the same in every session.
+    pub fn named(name: String) -> DataLine
+    {
+        let hash = name.bytes().fold(2_166_136_261u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(16_777_619));
+        let rgb = Hsva::new((hash % 360) as f32 / 360.0, 0.8, 0.9, 1.0).to_rgb();
+
+        DataLine
+        {
+            name,
+            data: Vec::new(),
+            band: Vec::new(),
+            rgb,
+        }
+    }
     pub fn len(&self) -> usize
     {
-        return self.data.len();
+        self.data.len()
     }
 
     pub fn clear(&mut self)
     {
         self.data.clear();
+        self.band.clear();
     }
     pub fn push(&mut self, element: [f64; 2])
     {
         self.data.push(element);
     }
+
+    // The band of the value just pushed, an empty run is where the next one starts.
+    pub fn push_band(&mut self, time: f64, band: Option<[f64; 2]>)
+    {
+        match band {
+            Some([low, high]) => {
+                if self.band.is_empty()
+                {
+                    self.band.push(Vec::new());
+                }
+                if let Some(run) = self.band.last_mut()
+                {
+                    run.push([time, low, high]);
+                }
+            }
+            None => {
+                if self.band.last().is_some_and(|run| !run.is_empty())
+                {
+                    self.band.push(Vec::new());
+                }
+            }
+        }
+    }
 }
 
 #[derive(PartialEq)]
 #[derive(Clone)]
 pub struct DataLineDependency
 {
-    pub index_old: [usize; 2],
-    pub index: [usize; 2],
+    pub index_old: [ChannelKey; 2],
+    pub index: [ChannelKey; 2],
     pub rgb: [f32; 3],
 }
 
 impl DataLineDependency
 {
-    pub fn new(x: usize, y: usize) -> DataLineDependency
+    pub fn new(x: ChannelKey, y: ChannelKey) -> DataLineDependency
     {
         DataLineDependency
         {
@@ -0,0 +1,91 @@
+// Messages sent from a reading thread to the GUI.
+
+use crate::take::Take;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame
+{
+    pub timestamp: f64,
+    pub values: Vec<Option<f64>>,
+    // Lowest and highest value of each channel over the interval, for an envelope.
+    pub bands: Vec<Option<[f64; 2]>>,
+    pub source_id: usize,
+}
+
+impl Frame
+{
+    pub fn new(source_id: usize, timestamp: f64, values: Vec<Option<f64>>) -> Frame
+    {
+        Frame
+        {
+            timestamp,
+            values,
+            bands: Vec::new(),
+            source_id,
+        }
+    }
+}
+
+// Frame counters of one reading thread.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats
+{
+    pub valid: u64,
+    // Frames with a wrong checksum, they are also counted as dropped.
+    pub corrupt: u64,
+    pub dropped: u64,
+    // Received bytes that are not valid UTF-8 text, each is replaced with `�`.
+    pub invalid: u64,
+}
+
+// Messages sent from the GUI to a reading thread.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command
+{
+    Stop,
+    // Pulses DTR and RTS to reset the device.
+    Reset,
+    // Writes the data to the device and marks the time with the text.
+    Send { data: Vec<u8>, text: String },
+    // Takes the channel of the name, or the index when it is not named, its own way instead of the way of the source.
+    Take { channel: String, take: Take },
+}
+
+// A received line, or a packet of a binary format, as it came.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TerminalLine
+{
+    pub timestamp: f64,
+    pub data: Vec<u8>,
+    // The line gave no frame.
+    pub failed: bool,
+}
+
+impl TerminalLine
+{
+    // The data as text, or as hex bytes like `01 A0 FF`.
+    pub fn text(&self, hex: bool) -> String
+    {
+        if hex
+        {
+            return self.data.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(" ");
+        }
+        String::from_utf8_lossy(&self.data).to_string()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event
+{
+    Frame(Frame),
+    // Names the channel at `index` of the frames sent by `source_id`.
+    Channel { source_id: usize, index: usize, name: String },
+    Stats { source_id: usize, stats: Stats },
+    Status { source_id: usize, text: String },
+    Warning { source_id: usize, text: String },
+    Terminal { source_id: usize, lines: Vec<TerminalLine> },
+    // Marks a moment on the time axis.
+    Marker { source_id: usize, timestamp: f64, text: String },
+    // Stops the reading thread of `source_id`.
+    Error { source_id: usize, text: String },
+}
@@ -1,22 +1,35 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 use std::thread;
 use std::time::{Duration, Instant};
 use std::sync::mpsc;
 
 use eframe::egui;
-use egui_plot;
-use egui_plot::{Line, Points, PlotPoints, Legend};
+use egui_plot::{Line, Points, PlotPoints, Polygon, Legend, VLine};
 use serialport::{available_ports, SerialPortType};
 
 use native_dialog::FileDialog;
+use event::{Command, Event, Stats, TerminalLine};
 use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Write;
 
 mod readport;
+mod binary;
+mod checksum;
+mod clock;
+mod console;
+mod source;
 mod dataline;
-const NAME_FILE_SETTINGS: &str = "./settings.log";
+mod event;
+mod parser;
+mod replay;
+mod settings;
+mod simulator;
+mod take;
+
+// Lines kept in the terminal, the oldest are dropped.
+const TERMINAL_LINES: usize = 1000;
 
 fn main()-> Result<(), eframe::Error>
 {
@@ -25,32 +38,77 @@ fn main()-> Result<(), eframe::Error>
     viewport: egui::ViewportBuilder::default().with_inner_size([320.0, 240.]),
     ..Default::default()
 };
-   return eframe::run_native(
+   let mut app = MyApp::default();
+
+   // `--stdin` or `--port <port>` starts reading right away, like `some_logger | serial-plotter --stdin`.
+   // Every `--port` after the first adds a source with the settings of the last one.
+   let mut args = std::env::args().skip(1);
+   let mut ports = Vec::new();
+   while let Some(arg) = args.next()
+   {
+       match arg.as_str() {
+           "--stdin" => ports.push("stdin://".to_string()),
+           "--port" => ports.push(args.next().unwrap_or_default()),
+           _ => (),
+       }
+   }
+   if !ports.is_empty()
+   {
+       app.sources.truncate(ports.len());
+       for (i, port) in ports.into_iter().enumerate()
+       {
+           if i == app.sources.len()
+           {
+               app.sources.push(app.sources[i - 1].clone());
+           }
+           app.sources[i].name_port = port;
+       }
+       app.start();
+   }
+
+   eframe::run_native(
        "serial-plotter",
        options,
-       Box::new(|_cc| Ok(Box::new(MyApp::default()))),
-   );
+       Box::new(|_cc| Ok(Box::new(app))),
+   )
 }
 
 
+// State of the reading thread of one source.
+struct SourceState
+{
+    command_tx: mpsc::Sender<Command>,
+    run: bool,
+    error_str: String,
+    status_str: String,
+    stats: Stats,
+}
+
 struct MyApp
 {
-    xyz: BTreeMap<usize, dataline::DataLine>,
+    xyz: BTreeMap<dataline::ChannelKey, dataline::DataLine>,
+    // Names the sources gave their channels, the way of taking a channel is saved under it.
+    channel_names: BTreeMap<dataline::ChannelKey, String>,
     dependency: BTreeMap<usize, dataline::DataLineDependency>,
     get_time: f64,
-    rtx: (mpsc::Sender<(String, f64)>, mpsc::Receiver<(String, f64)>),
-    run_rtx: (mpsc::Sender<bool>, mpsc::Receiver<bool>),
-    name_port: String,
-    speed: u32,
-    time: f64,
+    rtx: (mpsc::Sender<Event>, mpsc::Receiver<Event>),
+    // Settings of every source, `source` is the one shown in the settings panel.
+    sources: Vec<settings::Settings>,
+    source: usize,
+    states: Vec<SourceState>,
     time_start: Instant,
     run: bool,
-    chart_xyz_bool: Vec<bool>,
+    chart_xyz_bool: BTreeMap<dataline::ChannelKey, bool>,
+    // Moments marked on the time axis, as (time, text).
+    markers: Vec<(f64, String)>,
     chart_dependency_bool: Vec<bool>,
     settings: bool,
-    send: u32,
-    error: (mpsc::Sender<String>, mpsc::Receiver<String>),
-    error_str:String,
+    console: bool,
+    transmit: console::Console,
+    // Received lines as (source, line), shown in the terminal.
+    terminal: bool,
+    terminal_hex: bool,
+    terminal_lines: VecDeque<(usize, TerminalLine)>,
     info: bool,
     save_file: (bool, String),
 }
@@ -59,51 +117,84 @@ impl Default for MyApp
 {
     fn default() -> Self
     {
-        let mut name_port = "".to_string();
-        let mut speed = 9_600;
-        let mut time = 1.0;
-        let mut send = 1;
-
-
-        let file = File::open(NAME_FILE_SETTINGS);
-        if !file.is_err()
-        {
-            let mut contents = String::new();
-            let _ = file.unwrap().read_to_string(&mut contents);
-            let s = &contents.split("\n").map(|s| s.to_string()).collect::<Vec<_>>();
-            name_port = s[0].clone();
-            speed = s[1].parse().unwrap();
-            time = s[2].parse().unwrap();
-            send = s[3].parse().unwrap();
-        }
-
-
         Self
         {
             xyz: BTreeMap::new(),
+            channel_names: BTreeMap::new(),
             dependency: BTreeMap::new(),
             get_time: 0.0,
             time_start: Instant::now(),
             rtx: mpsc::channel(),
-            run_rtx: mpsc::channel(),
             run: false,
-            chart_xyz_bool: Vec::new(),
+            chart_xyz_bool: BTreeMap::new(),
+            markers: Vec::new(),
             chart_dependency_bool: Vec::new(),
             settings: false,
-            error: mpsc::channel(),
-            error_str: "".to_string(),
+            console: false,
+            transmit: console::Console::default(),
+            terminal: false,
+            terminal_hex: false,
+            terminal_lines: VecDeque::new(),
             info: true,
             save_file: (false, "~/Desktop".to_string()),
-            name_port: name_port,
-            speed: speed,
-            time: time,
-            send: send,
+            sources: settings::Settings::load(),
+            source: 0,
+            states: Vec::new(),
         }
 
 
     }
 }
 
+impl MyApp
+{
+    // Channels of several sources are named after their source.
+    fn channel_name(&self, source_id: usize, name: &str) -> String
+    {
+        if self.sources.len() < 2
+        {
+            return name.to_string();
+        }
+        self.sources[source_id].prefix() + "/" + name
+    }
+
+    fn start(&mut self)
+    {
+        self.rtx  = mpsc::channel();
+        self.xyz = BTreeMap::new();
+        self.channel_names = BTreeMap::new();
+        self.dependency = BTreeMap::new();
+        self.markers = Vec::new();
+        self.terminal_lines = VecDeque::new();
+        // Packets of binary formats are shown as hex.
+        self.terminal_hex = self.sources.iter().any(|port_settings| port_settings.format.framing().is_some());
+        self.states = Vec::new();
+        self.time_start = Instant::now();
+
+        for (source_id, settings) in self.sources.iter().enumerate()
+        {
+            let mut u = readport::ReadPort::new(source_id, settings.clone(), self.time_start, self.rtx.0.clone());
+
+            self.states.push(SourceState
+            {
+                command_tx: u.command_tx(),
+                run: true,
+                error_str: "".to_string(),
+                status_str: "".to_string(),
+                stats: Stats::default(),
+            });
+
+            let _a = thread::spawn(move || {
+                u.read();
+            });
+        }
+
+        self.run = true;
+        self.chart_xyz_bool = BTreeMap::new();
+        self.chart_dependency_bool = Vec::new();
+    }
+}
+
 impl eframe::App for MyApp
 {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame)
@@ -115,12 +206,17 @@ impl eframe::App for MyApp
 
                     egui::widgets::global_dark_light_mode_switch(ui);
 
-                    if self.xyz.len() > 0 && ui.button("Info").clicked()
+                    if !self.xyz.is_empty() && ui.button("Info").clicked()
                     {
                         self.info = !self.info;
                     };
 
-                    if !&self.run
+                    if (self.run || !self.terminal_lines.is_empty()) && ui.button("Terminal").clicked()
+                    {
+                        self.terminal = !self.terminal;
+                    }
+
+                    if !self.run
                     {
                         if ui.button("Settings").clicked()
                         {
@@ -129,52 +225,34 @@ impl eframe::App for MyApp
 
                         if ui.button("Start").clicked()
                         {
-                            self.rtx  = mpsc::channel();
-                            let sender = self.rtx.0.clone();
-                            self.error  = mpsc::channel();
-                            let error = self.error.0.clone();
-                            self.run_rtx  = mpsc::channel();
-                            self.xyz = BTreeMap::new();
-                            self.dependency = BTreeMap::new();
-                            self.error_str = "".to_string();
-
-                            let mut u = readport::ReadPort::new(self.name_port.to_string(), self.speed, (self.time* 1000.0) as u64, sender, error);
-
-                            self.run_rtx.0 = u.run_tx();
-                            let t_send = self.send;
-
-                            let _a = thread::spawn(move || {
-                                u.read(t_send);
-                            });
-
-                            self.run = true;
-                            self.time_start = Instant::now();
-                            self.chart_xyz_bool = Vec::new();
-                            self.chart_dependency_bool = Vec::new();
-
-                            let mut file = File::create(NAME_FILE_SETTINGS).unwrap();
-                            let _ = file.write_all(&self.name_port.as_bytes());
-                            let _ = file.write_all(b"\n");
-                            let _ = file.write_all(&self.speed.to_string().as_bytes());
-                            let _ = file.write_all(b"\n");
-                            let _ = file.write_all(&self.time.to_string().as_bytes());
-                            let _ = file.write_all(b"\n");
-                            let _ = file.write_all(&self.send.to_string().as_bytes());
-                            let _ = file.write_all(b"\n");
+                            self.start();
+                            settings::Settings::save(&self.sources);
                         };
                     }
                     else
                     {
+                        if ui.button("Console").clicked()
+                        {
+                            self.console = !self.console;
+                        }
+
                         if ui.button("Stop").clicked()
                         {
-                            let _ = self.run_rtx.0.send(false);
+                            for state in &mut self.states
+                            {
+                                let _ = state.command_tx.send(Command::Stop);
+                                state.run = false;
+                            }
                             self.run = false;
                         };
                     }
 
                     if self.xyz.len() > 1 && ui.button("Add dependency").clicked()
                     {
-                        self.dependency.insert(self.dependency.len(),  dataline::DataLineDependency::new(0, 1));
+                        let mut keys = self.xyz.keys();
+                        let x = *keys.next().unwrap();
+                        let y = *keys.next().unwrap();
+                        self.dependency.insert(self.dependency.len(),  dataline::DataLineDependency::new(x, y));
 
                         if self.chart_dependency_bool.len() < self.dependency.len()
                         {
@@ -182,7 +260,7 @@ impl eframe::App for MyApp
                         }
                     }
 
-                    if self.xyz.len() > 0
+                    if !self.xyz.is_empty()
                     {
                          ui.label("Total time: ".to_string() + &(self.get_time as u64 / 60 / 60 ).to_string() + ":" + &(self.get_time as u64 / 60 % 60 ).to_string() + ":" + &(self.get_time as u64 % 60 ).to_string());
                     }
@@ -191,93 +269,432 @@ impl eframe::App for MyApp
 
 
 
+        if self.run && self.console
+        {
+            egui::TopBottomPanel::bottom("console")
+                .show(ctx, |ui| {
+                    let mut command = None;
+
+                    ui.horizontal(|ui| {
+                        if self.sources.len() > 1
+                        {
+                            ui.label("To:");
+                            for i in 0..self.sources.len()
+                            {
+                                ui.selectable_value(&mut self.source, i, self.sources[i].prefix());
+                            }
+                        }
+
+                        let port_settings = &mut self.sources[self.source];
+                        let response = ui.add(egui::TextEdit::singleline(&mut self.transmit.text).hint_text(if port_settings.send_hex { "01 A0 ff" } else { "command" }).desired_width(ui.available_width()/2.0));
+                        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                        {
+                            command = Some(self.transmit.text.to_string());
+                            response.request_focus();
+                        }
+                        if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::ArrowUp))
+                        {
+                            self.transmit.previous();
+                        }
+                        if response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::ArrowDown))
+                        {
+                            self.transmit.next();
+                        }
+
+                        if ui.button("Send").clicked()
+                        {
+                            command = Some(self.transmit.text.to_string());
+                        }
+
+                        ui.checkbox(&mut port_settings.send_hex, "hex");
+                        if !port_settings.send_hex
+                        {
+                            egui::ComboBox::from_id_source("line_ending")
+                                .selected_text(port_settings.line_ending.name())
+                                .show_ui(ui, |ui| {
+                                    for line_ending in console::LineEnding::ALL
+                                    {
+                                        ui.selectable_value(&mut port_settings.line_ending, line_ending, line_ending.name());
+                                    }
+                                });
+                        }
+                    });
+
+                    ui.horizontal_wrapped(|ui| {
+                        let port_settings = &mut self.sources[self.source];
+                        ui.label("Macros:");
+
+                        let mut remove = None;
+                        for (i, item) in port_settings.macros.iter().enumerate()
+                        {
+                            let response = ui.button(&item.name).on_hover_text(item.command.to_string() + "\nright click removes it");
+                            if response.clicked()
+                            {
+                                command = Some(item.command.to_string());
+                            }
+                            if response.secondary_clicked()
+                            {
+                                remove = Some(i);
+                            }
+                        }
+
+                        ui.add(egui::TextEdit::singleline(&mut self.transmit.macro_name).hint_text("name").desired_width(80.0));
+                        if ui.button("Save macro").clicked() && !self.transmit.macro_name.is_empty() && !self.transmit.text.is_empty()
+                        {
+                            port_settings.macros.push(console::Macro { name: self.transmit.macro_name.to_string(), command: self.transmit.text.to_string() });
+                            self.transmit.macro_name.clear();
+                            settings::Settings::save(&self.sources);
+                        }
+                        else if let Some(i) = remove
+                        {
+                            port_settings.macros.remove(i);
+                            settings::Settings::save(&self.sources);
+                        }
+                    });
+
+                    if !self.transmit.history.is_empty()
+                    {
+                        ui.collapsing("History", |ui| {
+                            egui::ScrollArea::vertical().max_height(100.0).show(ui, |ui| {
+                                for item in self.transmit.history.iter().rev()
+                                {
+                                    if ui.selectable_label(false, item).clicked()
+                                    {
+                                        self.transmit.text = item.to_string();
+                                    }
+                                }
+                            });
+                        });
+                    }
+
+                    let Some(command) = command.filter(|command| !command.is_empty()) else
+                    {
+                        return;
+                    };
+
+                    let port_settings = &self.sources[self.source];
+                    match console::encode(&command, port_settings.line_ending, port_settings.send_hex) {
+                        Ok(data) => {
+                            let _ = self.states[self.source].command_tx.send(Command::Send { data, text: command.to_string() });
+                            self.transmit.push_history(&command);
+                            self.transmit.text.clear();
+                        }
+                        Err(error) => self.states[self.source].error_str = error,
+                    }
+                });
+        }
+
+        if self.terminal
+        {
+            egui::TopBottomPanel::bottom("terminal")
+                .resizable(true)
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.terminal_hex, "hex");
+                        if ui.button("Clear").clicked()
+                        {
+                            self.terminal_lines.clear();
+                        }
+                    });
+
+                    egui::ScrollArea::vertical()
+                        .auto_shrink([false, false])
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for (source_id, line) in &self.terminal_lines
+                            {
+                                let mut text = format!("{:.3} ", line.timestamp);
+                                // The source may have been removed since the line came.
+                                if let Some(port_settings) = self.sources.get(*source_id).filter(|_| self.sources.len() > 1)
+                                {
+                                    text += &(port_settings.prefix() + ": ");
+                                }
+                                text += &line.text(self.terminal_hex);
+
+                                // Lines that gave no frame are shown in red.
+                                let text = egui::RichText::new(text).monospace();
+                                ui.label(if line.failed { text.color(egui::Color32::RED) } else { text });
+                            }
+                        });
+                });
+        }
+
         if self.info
         {
             egui::SidePanel::left("list_plot_panel")
                 .resizable(true)
                 .width_range(0.0..=2000.0)
                 .show(ctx, |ui| {
-                if &self.error_str != ""
+                for (port_settings, state) in self.sources.iter().zip(&self.states)
                 {
-                    ui.label(egui::RichText::new(&self.error_str).color(egui::Color32::RED));
-                }
+                    if !state.error_str.is_empty()
+                    {
+                        ui.label(egui::RichText::new(&state.error_str).color(egui::Color32::RED));
+                    }
+                    else if !state.status_str.is_empty()
+                    {
+                        ui.label(&state.status_str);
+                    }
 
-                if self.run
-                {
-                    for i in  self.error.1.try_iter()
+                    if state.stats != Stats::default()
                     {
-                        self.error_str = i.to_string();
-                        if self.error_str.find("Warning!").is_some()
+                        ui.label("Frames: valid ".to_string() + &state.stats.valid.to_string() + ", corrupt " + &state.stats.corrupt.to_string() + ", dropped " + &state.stats.dropped.to_string());
+                        if state.stats.invalid > 0
                         {
-                            continue;
+                            ui.label("Invalid bytes: ".to_string() + &state.stats.invalid.to_string());
                         }
-                        self.run = false;
-                        return;
                     }
 
+                    if state.run
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Port: ".to_string() + &port_settings.name_port + " time: " + &port_settings.time.to_string() + "s, send: " + port_settings.send.name());
 
-                    let send = match &self.send {
-                        1 => "all",
-                        2 => "lost",
-                        3 => "mean",
-                        _ => "error"
-                    };
-
-                    ui.label("Port: ".to_string() + &self.name_port.to_string() + " time: " + &self.time.to_string() + "s, send: " + &send.to_string());
+                            if matches!(source::Target::parse(&port_settings.name_port), source::Target::Serial(_)) && ui.button("Pulse reset").clicked()
+                            {
+                                let _ = state.command_tx.send(Command::Reset);
+                            }
+                        });
+                    }
+                }
 
-                    if ui.button("Reset").clicked()
+                if self.run && ui.button("Reset").clicked()
+                {
+                    for xyz in self.xyz.values_mut()
                     {
-                        for i in 0..self.xyz.len()
-                        {
-                            self.xyz.get_mut(&i).expect("").clear();
-                        }
+                        xyz.clear();
                     }
                 }
 
 
                 if self.settings && !self.run
                 {
+                    ui.horizontal(|ui| {
+                        ui.label("Source:");
+                        for i in 0..self.sources.len()
+                        {
+                            ui.selectable_value(&mut self.source, i, (i + 1).to_string());
+                        }
+                        if ui.button("+").on_hover_text("Add a source").clicked()
+                        {
+                            self.sources.push(self.sources[self.source].clone());
+                            self.source = self.sources.len() - 1;
+                            self.sources[self.source].label = "".to_string();
+                        }
+                        if self.sources.len() > 1
+                        {
+                            if ui.button("Remove").clicked()
+                            {
+                                self.sources.remove(self.source);
+                                self.source = self.source.min(self.sources.len() - 1);
+                            }
+
+                            ui.label("name:");
+                            let port_settings = &mut self.sources[self.source];
+                            ui.add(egui::TextEdit::singleline(&mut port_settings.label).hint_text(port_settings.name_port.to_string()).clip_text(false).desired_width(ui.available_width()/3.0));
+                        }
+                    });
+
                     ui.horizontal(|ui|
                     {
                         ui.label("Port:");
                         egui::ComboBox::from_label("")
-                            .selected_text(&self.name_port)
+                            .selected_text(&self.sources[self.source].name_port)
                             .show_ui(ui, |ui| {
 
-                                let port = available_ports().expect("");
+                                let port = available_ports().unwrap_or_default();
                                 for p in &port
                                 {
-                                    ui.selectable_value(&mut self.name_port, p.port_name.to_string(),  &p.port_name.to_string());
+                                    ui.selectable_value(&mut self.sources[self.source].name_port, p.port_name.to_string(),  &p.port_name);
                                 };
+
+                                ui.separator();
+                                let target = source::Target::parse(&self.sources[self.source].name_port);
+                                if ui.selectable_label(matches!(target, source::Target::TcpClient(_)), "TCP client").clicked()
+                                {
+                                    self.sources[self.source].name_port = "tcp://localhost:4000".to_string();
+                                }
+                                if ui.selectable_label(matches!(target, source::Target::TcpServer(_)), "TCP server").clicked()
+                                {
+                                    self.sources[self.source].name_port = "tcp-listen://0.0.0.0:4000".to_string();
+                                }
+                                if ui.selectable_label(matches!(target, source::Target::Udp(_)), "UDP").clicked()
+                                {
+                                    self.sources[self.source].name_port = "udp://0.0.0.0:4000".to_string();
+                                }
+                                if ui.selectable_label(matches!(target, source::Target::Replay(_)), "File...").clicked()
+                                {
+                                    let open_file = FileDialog::new().set_location(&self.save_file.1).show_open_single_file().unwrap_or_default();
+                                    if let Some(open_file) = open_file
+                                    {
+                                        self.sources[self.source].name_port = "file://".to_string() + &open_file.to_string_lossy();
+                                    }
+                                }
+                                if ui.selectable_label(matches!(target, source::Target::Simulated), "Simulated").clicked()
+                                {
+                                    self.sources[self.source].name_port = "sim://".to_string();
+                                }
+                                if ui.selectable_label(target == source::Target::Stdin, "Standard input").clicked()
+                                {
+                                    self.sources[self.source].name_port = "stdin://".to_string();
+                                }
+                                if ui.selectable_label(matches!(target, source::Target::Pipe(_)), "Pipe").clicked()
+                                {
+                                    self.sources[self.source].name_port = "pipe:///tmp/serial-plotter".to_string();
+                                }
                             });
 
+                        let target = source::Target::parse(&self.sources[self.source].name_port);
+                        if target.is_network() || matches!(target, source::Target::Pipe(_))
+                        {
+                            ui.add(egui::TextEdit::singleline(&mut self.sources[self.source].name_port).clip_text(false).desired_width(ui.available_width()/3.0));
+                        }
+                        if matches!(target, source::Target::Udp(_))
+                        {
+                            ui.checkbox(&mut self.sources[self.source].group_senders, "channels per sender");
+                        }
+                        if matches!(target, source::Target::Replay(_))
+                        {
+                            let mut fastest = self.sources[self.source].replay_speed == 0.0;
+                            if ui.checkbox(&mut fastest, "as fast as possible").changed()
+                            {
+                                self.sources[self.source].replay_speed = if fastest { 0.0 } else { 1.0 };
+                            }
+                            if !fastest
+                            {
+                                ui.add(egui::DragValue::new(&mut self.sources[self.source].replay_speed).range(0.01..=1000.0).speed(0.1).prefix("x"));
+                            }
+                        }
+
 
-                        let mut tmp_value = self.speed.to_string();
+                        let mut tmp_value = self.sources[self.source].speed.to_string();
                         ui.label("speed:");
                         let _ = ui.add(egui::TextEdit::singleline(&mut tmp_value).clip_text(false).desired_width(ui.available_width()/3.0));
-                        if tmp_value == ""
+                        if tmp_value.is_empty()
                         {
-                            self.speed = 0;
+                            self.sources[self.source].speed = 0;
                         }
                         else
                         {
-                            self.speed = tmp_value.parse().unwrap()
+                            self.sources[self.source].speed = tmp_value.parse().unwrap()
                         }
 
                     });
 
+                    if matches!(source::Target::parse(&self.sources[self.source].name_port), source::Target::Serial(_))
+                    {
+                        ui.horizontal(|ui| {
+                            let port_settings = &mut self.sources[self.source];
+                            ui.label("Line:");
+                            egui::ComboBox::from_id_source("line")
+                                .selected_text(port_settings.line())
+                                .show_ui(ui, |ui| {
+                                    for data_bits in 5..=8
+                                    {
+                                        ui.selectable_value(&mut port_settings.data_bits, data_bits, data_bits.to_string() + " data bits");
+                                    }
+                                    ui.separator();
+                                    for parity in source::Parity::ALL
+                                    {
+                                        ui.selectable_value(&mut port_settings.parity, parity, "parity ".to_string() + parity.name());
+                                    }
+                                    ui.separator();
+                                    for stop_bits in 1..=2
+                                    {
+                                        ui.selectable_value(&mut port_settings.stop_bits, stop_bits, stop_bits.to_string() + " stop bits");
+                                    }
+                                });
+
+                            ui.label("flow control:");
+                            for flow_control in source::FlowControl::ALL
+                            {
+                                ui.radio_value(&mut port_settings.flow_control, flow_control, flow_control.name());
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            let port_settings = &mut self.sources[self.source];
+                            ui.label("On open:");
+                            ui.checkbox(&mut port_settings.dtr, "DTR");
+                            ui.checkbox(&mut port_settings.rts, "RTS");
+                            ui.add(egui::DragValue::new(&mut port_settings.settle).range(0.0..=10.0).speed(0.1).prefix("settle, s: "));
+
+                            ui.label("reconnect:");
+                            for reconnect in source::Reconnect::ALL
+                            {
+                                ui.radio_value(&mut port_settings.reconnect, reconnect, reconnect.name());
+                            }
+                        });
+                    }
+
+                    if source::Target::parse(&self.sources[self.source].name_port) == source::Target::Simulated
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Signal:");
+                            egui::ComboBox::from_id_source("sim_waveform")
+                                .selected_text(self.sources[self.source].sim_waveform.name())
+                                .show_ui(ui, |ui| {
+                                    for waveform in simulator::Waveform::ALL
+                                    {
+                                        ui.selectable_value(&mut self.sources[self.source].sim_waveform, waveform, waveform.name());
+                                    }
+                                });
+                            if self.sources[self.source].format.framing().is_none()
+                            {
+                                ui.add(egui::DragValue::new(&mut self.sources[self.source].sim_channels).range(1..=64).prefix("channels: "));
+                            }
+                            ui.add(egui::DragValue::new(&mut self.sources[self.source].sim_rate).range(0.1..=100_000.0).prefix("rate, Hz: "));
+                            ui.add(egui::DragValue::new(&mut self.sources[self.source].sim_frequency).range(0.01..=1000.0).speed(0.1).prefix("frequency, Hz: "));
+                        });
+                    }
+
                     ui.collapsing("Properities port", |ui| {
-                        let port = available_ports().expect("");
+                        match source::Target::parse(&self.sources[self.source].name_port) {
+                            source::Target::TcpClient(address) => {
+                                ui.label("Type: TCP client");
+                                ui.label("Address: ".to_string() + &address);
+                                return;
+                            }
+                            source::Target::TcpServer(address) => {
+                                ui.label("Type: TCP server");
+                                ui.label("Address: ".to_string() + &address);
+                                return;
+                            }
+                            source::Target::Udp(address) => {
+                                ui.label("Type: UDP");
+                                ui.label("Address: ".to_string() + &address);
+                                return;
+                            }
+                            source::Target::Replay(path) => {
+                                ui.label("Type: File");
+                                ui.label("Path: ".to_string() + &path);
+                                return;
+                            }
+                            source::Target::Simulated => {
+                                ui.label("Type: Simulated");
+                                return;
+                            }
+                            source::Target::Stdin => {
+                                ui.label("Type: Standard input");
+                                return;
+                            }
+                            source::Target::Pipe(path) => {
+                                ui.label("Type: Pipe");
+                                ui.label("Path: ".to_string() + &path);
+                                return;
+                            }
+                            source::Target::Serial(_) => (),
+                        }
 
-                        let p = port.iter().find(|&x| x.port_name.to_string() == self.name_port);
+                        let port = available_ports().unwrap_or_default();
 
-                        if p == None
+                        let p = port.iter().find(|&x| x.port_name == self.sources[self.source].name_port);
+
+                        let Some(p) = p else
                         {
                             ui.label("Failed to open port".to_string());
                             return;
-                        }
-
-                        let p = p.unwrap();
+                        };
 
                         match &p.port_type {
                             SerialPortType::UsbPort(info) => {
@@ -287,7 +704,7 @@ impl eframe::App for MyApp
                                 ui.label("Manufacturer: ".to_string() + info.manufacturer.as_ref().map_or("", String::as_str));
                                 ui.label("Product: ".to_string() + info.product.as_ref().map_or("", String::as_str));
                                 #[cfg(feature = "usbportinfo-interface")]
-                                ui.label("Interface: ".to_string() + info.interface.as_ref().map_or("".to_string(), |x| format!("{:02x}", *x)));
+                                ui.label("Interface: ".to_string() + &info.interface.as_ref().map_or("".to_string(), |x| format!("{:02x}", *x)));
                             }
                             SerialPortType::BluetoothPort => {
                                 ui.label("Type: Bluetooth");
@@ -302,55 +719,152 @@ impl eframe::App for MyApp
 
                     });
 
-                    match &self.send {
-                        2 => ui.add(egui::DragValue::new(&mut self.time).range(0.002..=60.0).prefix("Time, s: ")),
-                        _ => ui.add(egui::DragValue::new(&mut self.time).range(0.1..=60.0).prefix("Time, s: "))
+                    match &self.sources[self.source].send {
+                        take::Take::Lost => ui.add(egui::DragValue::new(&mut self.sources[self.source].time).range(0.002..=60.0).prefix("Time, s: ")),
+                        _ => ui.add(egui::DragValue::new(&mut self.sources[self.source].time).range(0.1..=60.0).prefix("Time, s: "))
                     };
 
 
                     ui.horizontal(|ui| {
                         ui.label("Take:");
-                        ui.radio_value(&mut self.send, 1, "all");
-                        ui.radio_value(&mut self.send, 2, "lost");
-                        ui.radio_value(&mut self.send, 3, "mean");
+                        for take in take::Take::ALL
+                        {
+                            ui.radio_value(&mut self.sources[self.source].send, take, take.name());
+                        }
                     });
 
                     ui.horizontal(|ui| {
-                        if self.xyz.len() > 0 && ui.button("Save").clicked()
+                        ui.label("Format:");
+                        for format in parser::Format::ALL
+                        {
+                            ui.radio_value(&mut self.sources[self.source].format, format, format.name());
+                        }
+
+                        if matches!(self.sources[self.source].format, parser::Format::Line | parser::Format::Labeled)
+                        {
+                            egui::ComboBox::from_id_source("delimiter")
+                                .selected_text(self.sources[self.source].delimiter.name())
+                                .show_ui(ui, |ui| {
+                                    for delimiter in parser::Delimiter::ALL
+                                    {
+                                        ui.selectable_value(&mut self.sources[self.source].delimiter, delimiter, delimiter.name());
+                                    }
+                                });
+                        }
+
+                        if self.sources[self.source].format.framing().is_some()
+                        {
+                            ui.label("layout:");
+                            ui.add(egui::TextEdit::singleline(&mut self.sources[self.source].layout).clip_text(false).desired_width(ui.available_width()/3.0));
+                            ui.checkbox(&mut self.sources[self.source].big_endian, "big endian");
+                        }
+                    });
+
+                    if self.sources[self.source].format.framing().is_none()
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Text lines:");
+                            for text_lines in parser::TextLines::ALL
+                            {
+                                ui.radio_value(&mut self.sources[self.source].text_lines, text_lines, text_lines.name());
+                            }
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Lines end with:");
+                            for terminator in parser::Terminator::ALL
+                            {
+                                ui.radio_value(&mut self.sources[self.source].terminator, terminator, terminator.name());
+                            }
+                            if self.sources[self.source].terminator == parser::Terminator::Custom
+                            {
+                                ui.add(egui::DragValue::new(&mut self.sources[self.source].terminator_byte).range(1..=127).hexadecimal(2, false, true).prefix("0x"));
+                            }
+                        });
+                    }
+
+                    if self.sources[self.source].format == parser::Format::Column
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("Frame ends at:");
+                            for separator in parser::FrameSeparator::ALL
+                            {
+                                ui.radio_value(&mut self.sources[self.source].frame_separator, separator, separator.name());
+                            }
+
+                            let port_settings = &mut self.sources[self.source];
+                            match port_settings.frame_separator {
+                                parser::FrameSeparator::Blank => (),
+                                parser::FrameSeparator::Sentinel => _ = ui.add(egui::TextEdit::singleline(&mut port_settings.frame_sentinel).desired_width(60.0)),
+                                parser::FrameSeparator::Count => _ = ui.add(egui::DragValue::new(&mut port_settings.frame_count).range(1..=1000).suffix(" values")),
+                                parser::FrameSeparator::Gap => _ = ui.add(egui::DragValue::new(&mut port_settings.frame_gap).range(0.0..=60.0).speed(0.1).suffix(" s")),
+                            }
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Time field:");
+                        ui.add(egui::TextEdit::singleline(&mut self.sources[self.source].time_field).clip_text(false).desired_width(ui.available_width()/4.0));
+
+                        for unit in clock::TimeUnit::ALL
+                        {
+                            ui.radio_value(&mut self.sources[self.source].time_unit, unit, unit.name());
+                        }
+
+                        egui::ComboBox::from_id_source("time_wrap")
+                            .selected_text(if self.sources[self.source].time_wrap == 0 { "no wrap".to_string() } else { "wraps at ".to_string() + &self.sources[self.source].time_wrap.to_string() + " bit" })
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.sources[self.source].time_wrap, 0, "no wrap");
+                                ui.selectable_value(&mut self.sources[self.source].time_wrap, 16, "wraps at 16 bit");
+                                ui.selectable_value(&mut self.sources[self.source].time_wrap, 32, "wraps at 32 bit");
+                            });
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Checksum:");
+                        for checksum in checksum::Checksum::ALL
+                        {
+                            ui.radio_value(&mut self.sources[self.source].checksum, checksum, checksum.name());
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        if !self.xyz.is_empty() && ui.button("Save").clicked()
                         {
                             let save_file = FileDialog::new().set_location(&self.save_file.1).show_save_single_file().unwrap();
 
-                            if save_file == Option::None
+                            let Some(save_file) = save_file else
                             {
                                 return;
-                            }
-                            self.save_file.1 = save_file.expect("error save file").into_os_string().into_string().unwrap();
+                            };
+                            self.save_file.1 = save_file.into_os_string().into_string().unwrap();
 
                             let mut file = File::create(&self.save_file.1).unwrap();
 
                             let _ = file.write_all(b"t,s\t");
 
 
-                            for i in 0..self.xyz.len()
+                            for xyz in self.xyz.values()
                             {
-                                let red = self.xyz.get(&i).expect("").name.to_string() + "\t";
+                                let red = xyz.name.to_string() + "\t";
 
                                 let _ = file.write_all(red.as_bytes());
                             };
                             let _ = file.write_all(b"\n");
 
-                            let max = self.xyz.iter().min_by(|a, b| a.1.len().cmp(&b.1.len())).unwrap().1.len();
-
-
-                            for j in 0..max
+                            // Rows follow the times of all channels merged, a channel without a value at a time leaves its cell empty.
+                            let mut next = vec![0; self.xyz.len()];
+                            while let Some(time) = self.xyz.values().zip(&next).filter_map(|(xyz, &j)| xyz.data.get(j)).map(|point| point[0]).min_by(f64::total_cmp)
                             {
-                                let mut red = self.xyz[&(0 as usize)].data[j as usize][0].to_string() + "\t";
-                                for i in 0..self.xyz.len()
+                                let mut red = time.to_string() + "\t";
+                                for (xyz, j) in self.xyz.values().zip(next.iter_mut())
                                 {
-                                    if &self.xyz[&(i as usize)].len() > &(j as usize)
+                                    if let Some(point) = xyz.data.get(*j).filter(|point| point[0] == time)
                                     {
-                                        red += &(self.xyz[&(i as usize)].data[j as usize][1].to_string() + "\t");
+                                        red += &point[1].to_string();
+                                        *j += 1;
                                     }
+                                    red += "\t";
                                 }
                                 let _ = file.write_all(red.as_bytes());
                                 let _ = file.write_all(b"\n");
@@ -361,12 +875,39 @@ impl eframe::App for MyApp
 
                 egui::ScrollArea::vertical().show(ui, |ui| {
                     ui.add(egui::Separator::default().shrink(0.0));
-                    for i in 0..self.xyz.len()
+                    for (i, xyz) in self.xyz.iter_mut()
                     {
                         ui.horizontal(|ui| {
-                                ui.add(egui::Checkbox::new(&mut self.chart_xyz_bool[i], ""));
-                            let _ = ui.add(egui::TextEdit::singleline(&mut self.xyz.get_mut(&i).unwrap().name).clip_text(false).desired_width(ui.available_width()/3.0));
-                            let _ = egui::widgets::color_picker::color_edit_button_rgb(ui, &mut self.xyz.get_mut(&i).unwrap().rgb);
+                                ui.add(egui::Checkbox::new(self.chart_xyz_bool.entry(*i).or_insert(true), ""));
+                            let _ = ui.add(egui::TextEdit::singleline(&mut xyz.name).clip_text(false).desired_width(ui.available_width()/3.0));
+                            let _ = egui::widgets::color_picker::color_edit_button_rgb(ui, &mut xyz.rgb);
+
+                            // A channel is taken the way of its source unless it has its own.
+                            let (source_id, index) = *i;
+                            let Some(port_settings) = self.sources.get_mut(source_id) else
+                            {
+                                return;
+                            };
+                            let channel = self.channel_names.get(i).cloned().unwrap_or_else(|| index.to_string());
+                            let mut channel_take = port_settings.channel_takes.get(&channel).copied().unwrap_or(port_settings.send);
+                            egui::ComboBox::from_id_source(("take", source_id, index))
+                                .selected_text(channel_take.name())
+                                .show_ui(ui, |ui| {
+                                    for take in take::Take::ALL
+                                    {
+                                        ui.selectable_value(&mut channel_take, take, take.name());
+                                    }
+                                });
+
+                            if port_settings.channel_takes.get(&channel).copied().unwrap_or(port_settings.send) != channel_take
+                            {
+                                port_settings.channel_takes.insert(channel.to_string(), channel_take);
+                                if let Some(state) = self.states.get(source_id)
+                                {
+                                    let _ = state.command_tx.send(Command::Take { channel, take: channel_take });
+                                }
+                                settings::Settings::save(&self.sources);
+                            }
                         });
 
                     }
@@ -382,21 +923,21 @@ impl eframe::App for MyApp
                         ui.horizontal(|ui| {
                             ui.add(egui::Checkbox::new(&mut self.chart_dependency_bool[i], ""));
                             egui::ComboBox::from_id_source(i*2)
-                                .selected_text(format!("{:?}", &self.xyz.get(&self.dependency.get_mut(&i).expect(&i.to_string()).index[0]).unwrap().name.to_string()))
+                                .selected_text(format!("{:?}", &self.xyz.get(&self.dependency[&i].index[0]).unwrap().name))
                                 .show_ui(ui, |ui| {
-                                    for p in 0..self.xyz.len()
+                                    for (p, xyz) in self.xyz.iter()
                                     {
-                                        ui.selectable_value(&mut self.dependency.get_mut(&i).unwrap().index[0], p,  &self.xyz.get(&p).unwrap().name.to_string());
+                                        ui.selectable_value(&mut self.dependency.get_mut(&i).unwrap().index[0], *p,  &xyz.name);
 
                                     };
                                 });
 
                             egui::ComboBox::from_id_source(i*2+1)
-                                .selected_text(format!("{:?}", &self.xyz.get(&self.dependency.get_mut(&i).unwrap().index[1]).unwrap().name.to_string()))
+                                .selected_text(format!("{:?}", &self.xyz.get(&self.dependency[&i].index[1]).unwrap().name))
                                 .show_ui(ui, |ui| {
-                                    for p in 0..self.xyz.len()
+                                    for (p, xyz) in self.xyz.iter()
                                     {
-                                        ui.selectable_value(&mut self.dependency.get_mut(&i).unwrap().index[1], p,  &self.xyz.get(&p).unwrap().name.to_string());
+                                        ui.selectable_value(&mut self.dependency.get_mut(&i).unwrap().index[1], *p,  &xyz.name);
 
                                     };
                                 });
@@ -409,11 +950,10 @@ impl eframe::App for MyApp
                                 for j in i..self.dependency.len()-1
                                 {
                                     self.chart_dependency_bool[j] = self.chart_dependency_bool[j+1];
-                                    self.dependency.insert(j, self.dependency.get(&(&j+1)).unwrap().clone());
+                                    self.dependency.insert(j, self.dependency[&(j+1)].clone());
                                 }
                                 self.chart_dependency_bool.pop();
-                                self.dependency.remove_entry(&(&self.dependency.len()-1));
-                                return;
+                                self.dependency.remove_entry(&(self.dependency.len()-1));
                             }
                         });
                     }
@@ -421,7 +961,7 @@ impl eframe::App for MyApp
             });
         }
 
-        if self.dependency.len() > 0
+        if !self.dependency.is_empty()
         {
             egui::SidePanel::right("chart_dependency")
                 .resizable(true)
@@ -443,14 +983,14 @@ impl eframe::App for MyApp
                             let index_x = dependency.index[0];
                             let index_y = dependency.index[1];
 
-                            let size = if self.xyz.get(&index_x).expect("error plot").len() < self.xyz.get(&index_y).expect("error plot").len() { self.xyz.get(&index_x).expect("error plot").len() } else { self.xyz.get(&index_y).expect("error plot").len()};
+                            let size = self.xyz.get(&index_x).expect("error plot").len().min(self.xyz.get(&index_y).expect("error plot").len());
                             let line_points: PlotPoints = (0..size).map(|i| {
                                                                     let x = self.xyz.get(&index_x).expect("error plot").data[i][1];
                                                                     let y = self.xyz.get(&index_y).expect("error plot").data[i][1];
                                                                     [x, y]
                                                                 }).collect();
 
-                            let name_line = self.xyz.get(&index_y).unwrap().name.to_string() + &"(".to_string() + &self.xyz.get(&index_x).unwrap().name.to_string() + ")";
+                            let name_line = self.xyz.get(&index_y).unwrap().name.to_string() + "(" + &self.xyz.get(&index_x).unwrap().name + ")";
 
                             let line = Points::new(line_points).name(&name_line).color(egui::Color32::from_rgb((dependency.rgb[0] * 255.0) as u8, (dependency.rgb[1] * 255.0) as u8, (dependency.rgb[2] * 255.0) as u8));
 
@@ -459,8 +999,7 @@ impl eframe::App for MyApp
                                 .height(plot_height)
                                 .clamp_grid(true)
                                 .auto_bounds([true, true].into())
-                                .show(ui, |plot_ui| plot_ui.points(line))
-                                .response;
+                                .show(ui, |plot_ui| plot_ui.points(line));
                          };
                     }
                 });
@@ -470,34 +1009,72 @@ impl eframe::App for MyApp
             .show(ctx, |ui| {
                 if self.run
                 {
-                    for y in  self.rtx.1.try_iter()
+                    for event in  self.rtx.1.try_iter()
                     {
-                        match y.0.chars().collect::<Vec<_>>()[0] {
-                            't' => self.get_time = y.1,
-                            'y' => (|| {
-
-                                    let index = (y.0.chars().collect::<Vec<_>>()[1] as i32 - 0x30)  as usize;
-                                    match self.xyz.get(&(index)) {
-                                        Option::None => _ = self.xyz.insert(index, dataline::DataLine::new(index.to_string(), vec![[self.get_time, y.1]])),
-                                        _ => _ = self.xyz.get_mut(&(index)).unwrap().push([self.get_time, y.1]),
+                        match event {
+                            Event::Frame(frame) => {
+                                self.get_time = self.get_time.max(frame.timestamp);
+
+                                for (index, value) in frame.values.iter().enumerate()
+                                {
+                                    let Some(value) = value else
+                                    {
+                                        continue;
+                                    };
+
+                                    let key = (frame.source_id, index);
+                                    match self.xyz.get_mut(&key) {
+                                        Option::None => _ = self.xyz.insert(key, dataline::DataLine::new(self.channel_name(frame.source_id, &index.to_string()), vec![[frame.timestamp, *value]])),
+                                        Some(xyz) => xyz.push([frame.timestamp, *value]),
+                                    }
+
+                                    if let Some(xyz) = self.xyz.get_mut(&key)
+                                    {
+                                        xyz.push_band(frame.timestamp, frame.bands.get(index).copied().flatten());
                                     }
-                                })(),
-                            _ => todo!(),
+                                }
+                            }
+                            Event::Channel { source_id, index, name } => {
+                                self.channel_names.insert((source_id, index), name.to_string());
+                                let name = self.channel_name(source_id, &name);
+                                self.xyz.entry((source_id, index)).or_insert_with(|| dataline::DataLine::named(name));
+                            }
+                            Event::Stats { source_id, stats } => self.states[source_id].stats = stats,
+                            Event::Status { source_id, text } => self.states[source_id].status_str = text,
+                            Event::Warning { source_id, text } => self.states[source_id].error_str = text,
+                            Event::Terminal { source_id, lines } => {
+                                self.terminal_lines.extend(lines.into_iter().map(|line| (source_id, line)));
+                                while self.terminal_lines.len() > TERMINAL_LINES
+                                {
+                                    self.terminal_lines.pop_front();
+                                }
+                            }
+                            Event::Marker { source_id, timestamp, text } => {
+                                let text = self.channel_name(source_id, &text);
+                                self.markers.push((timestamp, text));
+                            }
+                            Event::Error { source_id, text } => {
+                                self.states[source_id].error_str = text;
+                                self.states[source_id].run = false;
+                                self.run = self.states.iter().any(|state| state.run);
+                            }
                         };
                     };
-                    ctx.request_repaint_after(Duration::from_millis((&self.time*1000.0) as u64));
+
+                    let time = self.sources.iter().map(|port_settings| port_settings.time).fold(f64::MAX, f64::min);
+                    ctx.request_repaint_after(Duration::from_millis((time*1000.0) as u64));
                 };
 
-                if self.xyz.len()>0
+                if !self.xyz.is_empty()
                 {
-                    if self.chart_xyz_bool.len() < self.xyz.len()
+                    for i in self.xyz.keys()
                     {
-                        self.chart_xyz_bool = vec![true; self.xyz.len()];
+                        self.chart_xyz_bool.entry(*i).or_insert(true);
                     }
 
                     let mut plot_show:f32 = 0.0;
 
-                    for i in &self.chart_xyz_bool
+                    for i in self.chart_xyz_bool.values()
                     {
                         plot_show += *i as u8 as f32;
                     }
@@ -506,21 +1083,37 @@ impl eframe::App for MyApp
 
                     for (i,xyz) in self.xyz.iter()
                     {
-                        if self.chart_xyz_bool[*i]
+                        if self.chart_xyz_bool[i]
                         {
-                            let line_points: PlotPoints = PlotPoints::new(xyz.data.to_vec().into_iter().collect());
+                            let line_points: PlotPoints = PlotPoints::new(xyz.data.clone());
                             let name_line = &xyz.name;
 
-                            let line = Line::new(line_points).name(&name_line).color(egui::Color32::from_rgb((xyz.rgb[0] * 255.0) as u8, (xyz.rgb[1] * 255.0) as u8, (xyz.rgb[2] * 255.0) as u8));
+                            let line = Line::new(line_points).name(name_line).color(egui::Color32::from_rgb((xyz.rgb[0] * 255.0) as u8, (xyz.rgb[1] * 255.0) as u8, (xyz.rgb[2] * 255.0) as u8));
 
-                            egui_plot::Plot::new("plot ".to_string() + &name_line)
+                            egui_plot::Plot::new("plot ".to_string() + name_line)
                                 .legend(Legend::default())
                                 .height(plot_height)
                                 .width(ui.available_width())
                                 .clamp_grid(true)
                                 .auto_bounds([true, true].into())
-                                .show(ui, |plot_ui| {plot_ui.line(line)})
-                                .response;
+                                .show(ui, |plot_ui| {
+                                    // The band of an envelope is shaded from the lowest to the highest values.
+                                    // Each step is its own quad, a polygon is only filled right when it is convex.
+                                    let faint = egui::Color32::from_rgba_unmultiplied((xyz.rgb[0] * 255.0) as u8, (xyz.rgb[1] * 255.0) as u8, (xyz.rgb[2] * 255.0) as u8, 40);
+                                    for run in &xyz.band
+                                    {
+                                        for step in run.windows(2)
+                                        {
+                                            let points: PlotPoints = vec![[step[0][0], step[0][1]], [step[1][0], step[1][1]], [step[1][0], step[1][2]], [step[0][0], step[0][2]]].into();
+                                            plot_ui.polygon(Polygon::new(points).fill_color(faint).stroke(egui::Stroke::NONE));
+                                        }
+                                    }
+                                    plot_ui.line(line);
+                                    for (time, text) in &self.markers
+                                    {
+                                        plot_ui.vline(VLine::new(*time).name(text).color(egui::Color32::GRAY));
+                                    }
+                                });
                          };
                     };
                 };
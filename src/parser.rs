@@ -0,0 +1,723 @@
+// Turns received text lines into frames.
+
+use crate::binary::Framing;
+use crate::checksum::Checksum;
+use crate::clock::Clock;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format
+{
+    // One value per line, a blank line ends the frame.
+    Column,
+    // One frame per line, values separated by a delimiter.
+    Line,
+    // One frame per line of `name:value` pairs, each name is its own channel.
+    Labeled,
+    // One JSON object per line, nested numbers are named by their path like `accel.x`.
+    Json,
+    // Binary packets framed with COBS, fields described by a layout.
+    Cobs,
+    // Binary packets framed with SLIP, fields described by a layout.
+    Slip,
+}
+
+impl Format
+{
+    pub const ALL: [Format; 6] = [Format::Column, Format::Line, Format::Labeled, Format::Json, Format::Cobs, Format::Slip];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Format::Column => "column",
+            Format::Line => "line",
+            Format::Labeled => "labeled",
+            Format::Json => "json",
+            Format::Cobs => "cobs",
+            Format::Slip => "slip",
+        }
+    }
+
+    pub fn framing(&self) -> Option<Framing>
+    {
+        match self {
+            Format::Cobs => Some(Framing::Cobs),
+            Format::Slip => Some(Framing::Slip),
+            _ => None,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format>
+    {
+        Format::ALL.into_iter().find(|format| format.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Delimiter
+{
+    // Any of comma, space or tab, like the Arduino IDE plotter.
+    Auto,
+    Comma,
+    Semicolon,
+    Space,
+    Tab,
+}
+
+impl Delimiter
+{
+    pub const ALL: [Delimiter; 5] = [Delimiter::Auto, Delimiter::Comma, Delimiter::Semicolon, Delimiter::Space, Delimiter::Tab];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Delimiter::Auto => "auto",
+            Delimiter::Comma => "comma",
+            Delimiter::Semicolon => "semicolon",
+            Delimiter::Space => "space",
+            Delimiter::Tab => "tab",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Delimiter>
+    {
+        Delimiter::ALL.into_iter().find(|delimiter| delimiter.name() == name)
+    }
+
+    pub fn split<'a>(&self, line: &'a str) -> Vec<&'a str>
+    {
+        match self {
+            Delimiter::Auto => line.split([',', ' ', '\t']).filter(|s| !s.is_empty()).collect(),
+            Delimiter::Comma => line.split(',').collect(),
+            Delimiter::Semicolon => line.split(';').collect(),
+            Delimiter::Space => line.split(' ').filter(|s| !s.is_empty()).collect(),
+            Delimiter::Tab => line.split('\t').collect(),
+        }
+    }
+}
+
+// Text that ends a received line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Terminator
+{
+    // A CR before it is dropped too, so CRLF lines are read as well.
+    Lf,
+    CrLf,
+    Cr,
+    // Any ASCII byte, given in the settings.
+    Custom,
+}
+
+impl Terminator
+{
+    pub const ALL: [Terminator; 4] = [Terminator::Lf, Terminator::CrLf, Terminator::Cr, Terminator::Custom];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Terminator::Lf => "lf",
+            Terminator::CrLf => "crlf",
+            Terminator::Cr => "cr",
+            Terminator::Custom => "custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Terminator>
+    {
+        Terminator::ALL.into_iter().find(|terminator| terminator.name() == name)
+    }
+
+    pub fn text(&self, byte: u8) -> String
+    {
+        match self {
+            Terminator::Lf => "\n".to_string(),
+            Terminator::CrLf => "\r\n".to_string(),
+            Terminator::Cr => "\r".to_string(),
+            Terminator::Custom => char::from(byte.clamp(1, 127)).to_string(),
+        }
+    }
+
+    // A complete line without what is left of its end.
+    pub fn line<'a>(&self, line: &'a str) -> &'a str
+    {
+        match self {
+            Terminator::Lf => line.strip_suffix('\r').unwrap_or(line),
+            _ => line,
+        }
+    }
+
+    // The same for a line as received bytes.
+    pub fn raw_line<'a>(&self, line: &'a [u8]) -> &'a [u8]
+    {
+        match self {
+            Terminator::Lf => line.strip_suffix(b"\r").unwrap_or(line),
+            _ => line,
+        }
+    }
+}
+
+// What ends a frame of the column format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameSeparator
+{
+    // An empty line.
+    Blank,
+    // A line with the sentinel text given in the settings.
+    Sentinel,
+    // The given number of values.
+    Count,
+    // No data for the given number of seconds.
+    Gap,
+}
+
+impl FrameSeparator
+{
+    pub const ALL: [FrameSeparator; 4] = [FrameSeparator::Blank, FrameSeparator::Sentinel, FrameSeparator::Count, FrameSeparator::Gap];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            FrameSeparator::Blank => "blank",
+            FrameSeparator::Sentinel => "sentinel",
+            FrameSeparator::Count => "count",
+            FrameSeparator::Gap => "gap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FrameSeparator>
+    {
+        FrameSeparator::ALL.into_iter().find(|separator| separator.name() == name)
+    }
+}
+
+// What is done with lines holding no numbers, like messages printed by the device between frames.
+// They never take the place of a value, so the channels stay as they are.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextLines
+{
+    Ignore,
+    // Shown in red in the terminal and as a warning.
+    Log,
+    // Marked on the time axis with the text of the line.
+    Marker,
+}
+
+impl TextLines
+{
+    pub const ALL: [TextLines; 3] = [TextLines::Ignore, TextLines::Log, TextLines::Marker];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            TextLines::Ignore => "ignore",
+            TextLines::Log => "log",
+            TextLines::Marker => "marker",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TextLines>
+    {
+        TextLines::ALL.into_iter().find(|text_lines| text_lines.name() == name)
+    }
+}
+
+// Values of one frame, with the time the device stamped it with if it did.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record
+{
+    pub timestamp: Option<f64>,
+    pub values: Vec<Option<f64>>,
+}
+
+impl Record
+{
+    pub fn new(values: Vec<Option<f64>>) -> Record
+    {
+        Record
+        {
+            timestamp: None,
+            values,
+        }
+    }
+}
+
+pub struct Parser
+{
+    format: Format,
+    delimiter: Delimiter,
+    clock: Clock,
+    checksum: Checksum,
+    separator: FrameSeparator,
+    sentinel: String,
+    count: usize,
+    frame: Vec<Option<f64>>,
+    // Channel names in the order they first appeared, the position is the channel index.
+    labels: Vec<String>,
+    new_labels: Vec<(usize, String)>,
+    // The first frame after opening the port is incomplete and is skipped.
+    synced: bool,
+    // A line of the current column frame failed its checksum.
+    broken: bool,
+    // The last line failed its checksum.
+    failed: bool,
+    corrupt: u64,
+}
+
+impl Parser
+{
+    pub fn new(settings: &Settings) -> Parser
+    {
+        Parser
+        {
+            format: settings.format,
+            delimiter: settings.delimiter,
+            clock: Clock::new(settings),
+            checksum: settings.checksum,
+            separator: settings.frame_separator,
+            sentinel: settings.frame_sentinel.trim().to_string(),
+            count: settings.frame_count.max(1),
+            broken: false,
+            failed: false,
+            corrupt: 0,
+            frame: Vec::new(),
+            labels: Vec::new(),
+            new_labels: Vec::new(),
+            synced: false,
+        }
+    }
+
+    // Returns a frame once one is complete, or an error for a line that holds no data.
+    // Such a line is left out of the frame in progress.
+    pub fn parse_line(&mut self, line: &str) -> Result<Option<Record>, String>
+    {
+        self.failed = false;
+        if self.format == Format::Column && self.ends_frame(line)
+        {
+            return Ok(self.end_column());
+        }
+
+        if !self.synced
+        {
+            // Column frames are synced by the line that ends them, frames of a fixed count can not be.
+            self.synced = self.format != Format::Column || self.separator == FrameSeparator::Count;
+            return Ok(None);
+        }
+
+        if line.trim().is_empty()
+        {
+            return Ok(None);
+        }
+
+        let Some(line) = self.checksum.check_text(line) else
+        {
+            self.failed = true;
+            if self.format == Format::Column
+            {
+                self.broken = true;
+                // The line still takes its place in a frame of a fixed count.
+                self.frame.push(None);
+                return Ok(self.end_counted());
+            }
+            else
+            {
+                self.corrupt += 1;
+            }
+            return Ok(None);
+        };
+
+        let mut record = match self.format {
+            Format::Column => return self.parse_column(line),
+            Format::Line => self.parse_delimited(line),
+            Format::Labeled => self.parse_labeled(line),
+            Format::Json => self.parse_json(line),
+            Format::Cobs | Format::Slip => Err("Warning! Incorrect received data".to_string()),
+        };
+
+        if let Ok(Some(record)) = &mut record
+        {
+            self.clock.take(record, &[]);
+        }
+        record
+    }
+
+    // Ends the column frame in progress, when the end is not marked by a line.
+    pub fn end_frame(&mut self) -> Option<Record>
+    {
+        if self.format != Format::Column
+        {
+            return None;
+        }
+        self.end_column()
+    }
+
+    // Takes the next line as the start of a frame.
+    pub fn sync(&mut self)
+    {
+        self.synced = true;
+    }
+
+    // Drops the frame in progress and skips the next incomplete one, after the port was reopened.
+    pub fn resync(&mut self)
+    {
+        self.synced = false;
+        self.broken = false;
+        self.frame.clear();
+        self.clock.restart();
+    }
+
+    // Whether the last line failed its checksum, also when its column frame is not complete yet.
+    pub fn failed(&self) -> bool
+    {
+        self.failed
+    }
+
+    // Number of frames dropped for a wrong checksum since the last call.
+    pub fn take_corrupt(&mut self) -> u64
+    {
+        std::mem::take(&mut self.corrupt)
+    }
+
+    // Channels named since the last call, as (index, name).
+    pub fn take_new_labels(&mut self) -> Vec<(usize, String)>
+    {
+        std::mem::take(&mut self.new_labels)
+    }
+
+    fn set_labeled(&mut self, frame: &mut Vec<Option<f64>>, label: &str, value: f64)
+    {
+        let index = self.label_index(label);
+        if frame.len() <= index
+        {
+            frame.resize(index + 1, None);
+        }
+        frame[index] = Some(value);
+    }
+
+    fn label_index(&mut self, label: &str) -> usize
+    {
+        if let Some(index) = self.labels.iter().position(|name| name == label)
+        {
+            return index;
+        }
+
+        self.labels.push(label.to_string());
+        self.new_labels.push((self.labels.len() - 1, label.to_string()));
+        self.labels.len() - 1
+    }
+
+    fn parse_column(&mut self, line: &str) -> Result<Option<Record>, String>
+    {
+        match line.trim().parse::<f64>() {
+            Ok(value) => {
+                self.frame.push(Some(value));
+                Ok(self.end_counted())
+            }
+            Err(_) => Err("Warning! Incorrect received data".to_string()),
+        }
+    }
+
+    fn ends_frame(&self, line: &str) -> bool
+    {
+        match self.separator {
+            FrameSeparator::Blank => line.is_empty(),
+            FrameSeparator::Sentinel => !self.sentinel.is_empty() && line.trim() == self.sentinel,
+            FrameSeparator::Count | FrameSeparator::Gap => false,
+        }
+    }
+
+    fn end_counted(&mut self) -> Option<Record>
+    {
+        if self.separator == FrameSeparator::Count && self.frame.len() >= self.count
+        {
+            return self.end_column();
+        }
+        None
+    }
+
+    fn end_column(&mut self) -> Option<Record>
+    {
+        let frame = std::mem::take(&mut self.frame);
+        let synced = self.synced;
+        self.synced = true;
+
+        if self.broken
+        {
+            self.broken = false;
+            self.corrupt += 1;
+            return None;
+        }
+
+        if !synced || frame.is_empty()
+        {
+            return None;
+        }
+
+        let mut record = Record::new(frame);
+        self.clock.take(&mut record, &[]);
+        Some(record)
+    }
+
+    fn parse_delimited(&mut self, line: &str) -> Result<Option<Record>, String>
+    {
+        let fields = self.delimiter.split(line);
+        let frame: Vec<Option<f64>> = fields.iter().map(|value| value.trim().parse::<f64>().ok()).collect();
+
+        // A line is data only when every field that is not empty holds a number, otherwise it is text.
+        let text = fields.iter().zip(&frame).any(|(field, value)| !field.trim().is_empty() && value.is_none());
+        if text || frame.iter().all(|value| value.is_none())
+        {
+            return Err("Warning! Incorrect received data".to_string());
+        }
+        Ok(Some(Record::new(frame)))
+    }
+
+    fn parse_labeled(&mut self, line: &str) -> Result<Option<Record>, String>
+    {
+        let mut record = Record::new(vec![None; self.labels.len()]);
+        let mut found = false;
+        let mut fields = Vec::new();
+
+        for field in self.delimiter.split(line)
+        {
+            if field.trim().is_empty()
+            {
+                continue;
+            }
+
+            // The value follows the last colon, names such as replayed captures may hold colons.
+            // A line is data only when every field is a name with a number, otherwise it is text.
+            let Some((label, Ok(value))) = field.rsplit_once(':').map(|(label, value)| (label.trim().to_string(), value.trim().parse::<f64>())) else
+            {
+                return Err("Warning! Incorrect received data".to_string());
+            };
+            fields.push((label, value));
+        }
+
+        for (label, value) in fields
+        {
+            if self.clock.is_field(&label)
+            {
+                record.timestamp = Some(self.clock.seconds(value));
+                continue;
+            }
+
+            self.set_labeled(&mut record.values, &label, value);
+            found = true;
+        }
+
+        if !found
+        {
+            return Err("Warning! Incorrect received data".to_string());
+        }
+        Ok(Some(record))
+    }
+
+    fn parse_json(&mut self, line: &str) -> Result<Option<Record>, String>
+    {
+        let Ok(serde_json::Value::Object(object)) = serde_json::from_str::<serde_json::Value>(line) else
+        {
+            return Err("Warning! Incorrect received data".to_string());
+        };
+
+        let mut fields = Vec::new();
+        flatten_json("", &serde_json::Value::Object(object), &mut fields);
+
+        let mut record = Record::new(vec![None; self.labels.len()]);
+        for (label, value) in fields
+        {
+            if self.clock.is_field(&label)
+            {
+                record.timestamp = Some(self.clock.seconds(value));
+                continue;
+            }
+            self.set_labeled(&mut record.values, &label, value);
+        }
+
+        if record.values.iter().all(|value| value.is_none())
+        {
+            return Err("Warning! Incorrect received data".to_string());
+        }
+        Ok(Some(record))
+    }
+}
+
+fn flatten_json(path: &str, value: &serde_json::Value, fields: &mut Vec<(String, f64)>)
+{
+    let join = |key: &str| if path.is_empty() { key.to_string() } else { path.to_string() + "." + key };
+
+    match value {
+        serde_json::Value::Number(number) => {
+            if let Some(number) = number.as_f64()
+            {
+                fields.push((path.to_string(), number));
+            }
+        }
+        serde_json::Value::Object(object) => {
+            for (key, value) in object
+            {
+                flatten_json(&join(key), value, fields);
+            }
+        }
+        serde_json::Value::Array(array) => {
+            for (i, value) in array.iter().enumerate()
+            {
+                flatten_json(&join(&i.to_string()), value, fields);
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::clock::TimeUnit;
+
+    fn settings(format: Format, delimiter: Delimiter, time_field: &str) -> Settings
+    {
+        Settings { format, delimiter, time_field: time_field.to_string(), ..Settings::default() }
+    }
+
+    // The first line after opening the port may be cut, so a parser is started with one.
+    fn synced(mut parser: Parser) -> Parser
+    {
+        assert_eq!(parser.parse_line("0"), Ok(None));
+        parser
+    }
+
+    fn values(values: &[Option<f64>]) -> Result<Option<Record>, String>
+    {
+        Ok(Some(Record::new(values.to_vec())))
+    }
+
+    #[test]
+    fn line()
+    {
+        let mut parser = synced(Parser::new(&settings(Format::Line, Delimiter::Auto, "")));
+        assert_eq!(parser.parse_line("1, 2 3\t4"), values(&[Some(1.0), Some(2.0), Some(3.0), Some(4.0)]));
+        assert!(parser.parse_line("ready").is_err());
+        assert!(parser.parse_line("Temperature 21.5").is_err());
+        assert_eq!(parser.parse_line(""), Ok(None));
+
+        let mut parser = synced(Parser::new(&settings(Format::Line, Delimiter::Comma, "")));
+        assert_eq!(parser.parse_line("1,,3"), values(&[Some(1.0), None, Some(3.0)]));
+        assert!(parser.parse_line(",,").is_err());
+
+        let mut parser = synced(Parser::new(&settings(Format::Line, Delimiter::Semicolon, "")));
+        assert_eq!(parser.parse_line("1.5;-2"), values(&[Some(1.5), Some(-2.0)]));
+    }
+
+    #[test]
+    fn labeled()
+    {
+        let mut parser = synced(Parser::new(&settings(Format::Labeled, Delimiter::Auto, "")));
+        assert_eq!(parser.parse_line("a:1 b:2"), values(&[Some(1.0), Some(2.0)]));
+        assert_eq!(parser.take_new_labels(), [(0, "a".to_string()), (1, "b".to_string())]);
+        assert_eq!(parser.parse_line("b:3"), values(&[None, Some(3.0)]));
+        assert!(parser.take_new_labels().is_empty());
+        assert!(parser.parse_line("a:1 2").is_err());
+        assert!(parser.parse_line("b: 3").is_err());
+
+        let mut parser = synced(Parser::new(&settings(Format::Labeled, Delimiter::Comma, "")));
+        assert_eq!(parser.parse_line("x: 1.5, y: -2"), values(&[Some(1.5), Some(-2.0)]));
+        assert!(parser.parse_line("state:ok").is_err());
+        assert!(parser.parse_line("x:1, state:ok").is_err());
+
+        // The value follows the last colon, as in the names of replayed captures.
+        let mut parser = synced(Parser::new(&settings(Format::Labeled, Delimiter::Tab, "")));
+        assert_eq!(parser.parse_line("dev/x:y:4\tz:5"), values(&[Some(4.0), Some(5.0)]));
+        assert_eq!(parser.take_new_labels(), [(0, "dev/x:y".to_string()), (1, "z".to_string())]);
+    }
+
+    #[test]
+    fn json()
+    {
+        let mut parser = synced(Parser::new(&settings(Format::Json, Delimiter::Auto, "t")));
+        assert_eq!(parser.parse_line(r#"{"t": 2.5, "accel": {"x": 1, "y": [2, 3]}, "name": "a"}"#), Ok(Some(Record { timestamp: Some(0.0), values: vec![Some(1.0), Some(2.0), Some(3.0)] })));
+        assert_eq!(parser.take_new_labels().into_iter().map(|(_, name)| name).collect::<Vec<_>>(), ["accel.x", "accel.y.0", "accel.y.1"]);
+        assert_eq!(parser.parse_line(r#"{"accel": {"y": [4]}}"#), values(&[None, Some(4.0), None]));
+        assert!(parser.parse_line(r#"{"name": "a"}"#).is_err());
+        assert!(parser.parse_line("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn checksum()
+    {
+        let mut parser = synced(Parser::new(&Settings { format: Format::Line, checksum: Checksum::Xor, ..Settings::default() }));
+        assert_eq!(parser.parse_line("1.2,3.4*28"), values(&[Some(1.2), Some(3.4)]));
+        assert!(!parser.failed());
+        assert_eq!(parser.parse_line("1.2,3.4*29"), Ok(None));
+        assert!(parser.failed());
+        assert_eq!(parser.parse_line("1.2,3.4"), Ok(None));
+        assert_eq!(parser.take_corrupt(), 2);
+
+        // A column frame with a broken line is dropped once it ends.
+        let mut parser = Parser::new(&Settings { format: Format::Column, checksum: Checksum::Xor, ..Settings::default() });
+        assert_eq!(parser.parse_line(""), Ok(None));
+        assert_eq!(parser.parse_line("1*31"), Ok(None));
+        assert!(!parser.failed());
+        assert_eq!(parser.parse_line("2*00"), Ok(None));
+        assert!(parser.failed());
+        assert_eq!(parser.take_corrupt(), 0);
+        assert_eq!(parser.parse_line(""), Ok(None));
+        assert_eq!(parser.take_corrupt(), 1);
+        assert_eq!(parser.parse_line("1*31"), Ok(None));
+        assert_eq!(parser.parse_line(""), values(&[Some(1.0)]));
+    }
+
+    #[test]
+    fn time_field()
+    {
+        let mut parser = synced(Parser::new(&Settings { format: Format::Line, time_field: "0".to_string(), time_unit: TimeUnit::Ms, ..Settings::default() }));
+        assert_eq!(parser.parse_line("1000,5"), Ok(Some(Record { timestamp: Some(0.0), values: vec![None, Some(5.0)] })));
+        assert_eq!(parser.parse_line("1250,6"), Ok(Some(Record { timestamp: Some(0.25), values: vec![None, Some(6.0)] })));
+
+        let mut parser = synced(Parser::new(&settings(Format::Labeled, Delimiter::Auto, "ms")));
+        assert_eq!(parser.parse_line("ms:3 a:1"), Ok(Some(Record { timestamp: Some(0.0), values: vec![Some(1.0)] })));
+        assert_eq!(parser.parse_line("a:2 ms:5"), Ok(Some(Record { timestamp: Some(2.0), values: vec![Some(2.0)] })));
+    }
+
+    #[test]
+    fn column()
+    {
+        // Frames end at a blank line, the first one is incomplete.
+        let mut parser = Parser::new(&Settings { format: Format::Column, ..Settings::default() });
+        assert_eq!(parser.parse_line("7"), Ok(None));
+        assert_eq!(parser.parse_line(""), Ok(None));
+        assert_eq!(parser.parse_line("1"), Ok(None));
+        assert_eq!(parser.parse_line("2"), Ok(None));
+        assert_eq!(parser.parse_line(""), values(&[Some(1.0), Some(2.0)]));
+
+        let mut parser = Parser::new(&Settings { format: Format::Column, frame_separator: FrameSeparator::Sentinel, frame_sentinel: "---".to_string(), ..Settings::default() });
+        assert_eq!(parser.parse_line("---"), Ok(None));
+        assert_eq!(parser.parse_line("1"), Ok(None));
+        assert!(parser.parse_line("ok").is_err());
+        assert_eq!(parser.parse_line(""), Ok(None));
+        assert_eq!(parser.parse_line(" --- "), values(&[Some(1.0)]));
+
+        // The first line may be cut, it is skipped as in the other formats.
+        let mut parser = Parser::new(&Settings { format: Format::Column, frame_separator: FrameSeparator::Count, frame_count: 2, ..Settings::default() });
+        assert_eq!(parser.parse_line("1"), Ok(None));
+        assert_eq!(parser.parse_line("2"), Ok(None));
+        assert_eq!(parser.parse_line("3"), values(&[Some(2.0), Some(3.0)]));
+
+        let mut parser = Parser::new(&Settings { format: Format::Column, frame_separator: FrameSeparator::Gap, ..Settings::default() });
+        parser.sync();
+        assert_eq!(parser.parse_line("1"), Ok(None));
+        assert_eq!(parser.parse_line(""), Ok(None));
+        assert_eq!(parser.end_frame(), Some(Record::new(vec![Some(1.0)])));
+        assert_eq!(parser.end_frame(), None);
+    }
+
+    #[test]
+    fn terminator()
+    {
+        assert_eq!(Terminator::Lf.line("1,2\r"), "1,2");
+        assert_eq!(Terminator::Cr.line("1,2"), "1,2");
+        assert_eq!(Terminator::Lf.text(b';'), "\n");
+        assert_eq!(Terminator::CrLf.text(b';'), "\r\n");
+        assert_eq!(Terminator::Cr.text(b';'), "\r");
+        assert_eq!(Terminator::Custom.text(b';'), ";");
+    }
+}
@@ -1,236 +1,779 @@
-use serialport;
 use std::time::{Duration, Instant};
 use std::thread;
 use std::str;
-use std::collections::HashMap;
+use std::io;
+use std::collections::BTreeMap;
 use std::sync::mpsc;
 
+use crate::binary::Decoder;
+use crate::event::{Command, Event, Frame, Stats, TerminalLine};
+use crate::parser::{FrameSeparator, Parser, Record, TextLines};
+use crate::settings::Settings;
+use crate::source::{Reconnect, Source, Target, UsbId};
+use crate::take::Take;
+
+// Milliseconds between attempts to open a lost port again.
+const RECONNECT_POLL: u64 = 500;
+
+// Parsing state of the bytes from one sender.
+struct Stream
+{
+    parser: Parser,
+    decoder: Option<Decoder>,
+    sparkle_heart: String,
+    // Bytes of a character split between reads.
+    undecoded: Vec<u8>,
+    // Bytes of the line in progress as they came, for the terminal.
+    raw: Vec<u8>,
+    // When data of this sender was last received, a gap after it ends a column frame.
+    received: Instant,
+    // Set when the channels of this sender are kept apart from the others, names them.
+    prefix: Option<String>,
+    // Index in the sent frames of each channel of this sender that has carried a value or a name.
+    channels: BTreeMap<usize, usize>,
+    // Host time of the first frame with a device time, where the device time axis of this sender starts.
+    device_start: Option<f64>,
+}
+
+impl Stream
+{
+    fn new(settings: &Settings, prefix: Option<String>) -> Result<Stream, String>
+    {
+        let decoder = match settings.format.framing() {
+            Some(framing) => Some(Decoder::new(framing, settings)?),
+            None => None,
+        };
+
+        Ok(Stream
+        {
+            parser: Parser::new(settings),
+            decoder,
+            sparkle_heart: "".to_string(),
+            undecoded: Vec::new(),
+            raw: Vec::new(),
+            received: Instant::now(),
+            prefix,
+            channels: BTreeMap::new(),
+            device_start: None,
+        })
+    }
+
+    // Datagrams start at a frame, so there is no incomplete frame to skip.
+    fn sync(&mut self)
+    {
+        self.parser.sync();
+        if let Some(decoder) = &mut self.decoder
+        {
+            decoder.sync();
+        }
+    }
+
+    // The device may have reset, so its time starts over and the data starts anywhere in a frame.
+    fn resync(&mut self)
+    {
+        self.device_start = None;
+        self.sparkle_heart.clear();
+        self.undecoded.clear();
+        self.raw.clear();
+        self.parser.resync();
+        if let Some(decoder) = &mut self.decoder
+        {
+            decoder.resync();
+        }
+    }
+}
+
+// Takes the complete lines out of the bytes, leaving the start of the next line.
+fn split_lines(bytes: &mut Vec<u8>, terminator: &[u8]) -> Vec<Vec<u8>>
+{
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i + terminator.len() <= bytes.len()
+    {
+        if bytes[i..].starts_with(terminator)
+        {
+            lines.push(bytes[start..i].to_vec());
+            i += terminator.len();
+            start = i;
+        }
+        else
+        {
+            i += 1;
+        }
+    }
+    bytes.drain(..start);
+    lines
+}
+
 pub struct ReadPort {
-        xyz: HashMap<i64, Vec<f64>>,
-        name_port: String,
-        speed: u32,
+        frames: Vec<Record>,
+        // Lines received since the last update, for the terminal.
+        terminal: Vec<TerminalLine>,
+        streams: BTreeMap<String, Stream>,
+        // Channels taken by senders whose channels are kept apart.
+        channels: usize,
+        // Names sent for the channels, by index.
+        names: BTreeMap<usize, String>,
+        settings: Settings,
+        // Milliseconds between updates of the display, the frames received meanwhile are taken together.
         time: u64,
         time_start: Instant,
-        n: u64,
-        tx: mpsc::Sender<(String, f64)>,
-        run_rtx: (mpsc::Sender<bool>, mpsc::Receiver<bool>),
-        error: mpsc::Sender<String>,
+        source_id: usize,
+        stats: Stats,
+        // Counts last sent to the display.
+        stats_sent: Stats,
+        tx: mpsc::Sender<Event>,
+        command_rtx: (mpsc::Sender<Command>, mpsc::Receiver<Command>),
 }
 
 
 impl ReadPort {
 
-    pub fn new(name_port: String, speed: u32, time: u64, tx: mpsc::Sender<(String, f64)>, error: mpsc::Sender<String>) -> ReadPort
+    // Sources read in one session share `time_start`, so their frames are on one time axis.
+    pub fn new(source_id: usize, settings: Settings, time_start: Instant, tx: mpsc::Sender<Event>) -> ReadPort
     {
         ReadPort{
-            name_port: name_port,
-            speed: speed,
-            time: time,
-            tx: tx,
-            n: 0,
-            error: error,
-            xyz: HashMap::new(),
-            time_start: Instant::now(),
-            run_rtx: mpsc::channel(),
+            time: (settings.time * 1000.0) as u64,
+            settings,
+            tx,
+            source_id,
+            stats: Stats::default(),
+            stats_sent: Stats::default(),
+            frames: Vec::new(),
+            terminal: Vec::new(),
+            streams: BTreeMap::new(),
+            channels: 0,
+            names: BTreeMap::new(),
+            time_start,
+            command_rtx: mpsc::channel(),
+        }
+    }
+
+    fn send(&self, event: Event)
+    {
+        let _ = self.tx.send(event);
+    }
+
+    fn send_frame(&self, timestamp: f64, values: Vec<Option<f64>>)
+    {
+        self.send(Event::Frame(Frame::new(self.source_id, timestamp, values)));
+    }
+
+    fn channels(&self) -> usize
+    {
+        self.frames.iter().map(|frame| frame.values.len()).max().unwrap_or(0)
+    }
+
+    fn host_time(&self) -> f64
+    {
+        self.time_start.elapsed().as_millis() as f64 / 1000.0
+    }
+
+    // Sends one frame for the interval with the channels taken `take` way, each from the values it received.
+    fn send_taken(&self, take: Take, takes: &[Take])
+    {
+        let host_time = self.host_time();
+        let timestamps: Vec<f64> = self.frames.iter().map(|frame| frame.timestamp.unwrap_or(host_time)).collect();
+        // The last value is shown at the time it came, the others in the middle of the frames taken.
+        let time = match take {
+            Take::Lost => timestamps[timestamps.len() - 1],
+            _ => timestamps.iter().sum::<f64>() / timestamps.len() as f64,
+        };
+
+        let mut frame = Frame::new(self.source_id, time, Vec::new());
+        for (i, &channel_take) in takes.iter().enumerate()
+        {
+            let received: Vec<f64> = match channel_take == take {
+                true => self.frames.iter().filter_map(|frame| frame.values.get(i).copied().flatten()).collect(),
+                false => Vec::new(),
+            };
+            frame.values.push(take.value(&received));
+            frame.bands.push(take.band(&received));
         }
+
+        if frame.values.iter().any(Option::is_some)
+        {
+            self.send(Event::Frame(frame));
+        }
+    }
+
+    // The way the channel at `index` is taken.
+    fn take(&self, index: usize) -> Take
+    {
+        let channel = self.names.get(&index).cloned().unwrap_or_else(|| index.to_string());
+        self.settings.channel_takes.get(&channel).copied().unwrap_or(self.settings.send)
     }
 
-    fn error_f(&self, s: String)
+    fn name_channel(&mut self, index: usize, name: String)
     {
-        self.error.send(s).unwrap();
+        self.names.insert(index, name.to_string());
+        self.send(Event::Channel { source_id: self.source_id, index, name });
     }
 
-    fn send_lost(&mut self)
+    // Sends every frame with the values of the channels taken as they are.
+    fn send_all(&self, takes: &[Take])
     {
-        let time = self.time_start.elapsed().as_millis() as f64 / 1000.0;
-        self.tx.send(('t'.to_string(),time)).unwrap();
+        let time = self.host_time();
 
-        for i in 0..self.xyz.len()
+        for frame in &self.frames
         {
-            if self.xyz[&i.try_into().unwrap()].len() == 0
+            let values: Vec<Option<f64>> = frame.values.iter().zip(takes).map(|(value, &take)| value.filter(|_| take == Take::All)).collect();
+            if values.iter().any(Option::is_some)
             {
-                continue;
+                self.send_frame(frame.timestamp.unwrap_or(time), values);
             }
+        }
+    }
 
-            let x = *self.xyz[&(i as i64)].last().unwrap();
-            self.tx.send(('y'.to_string()+&i.to_string(), x)).unwrap();
-            self.xyz.insert(i as i64, Vec::new());
+    // Maps a channel of the stream to its index in the sent frames, naming new channels of a sender.
+    fn channel(&mut self, stream: &mut Stream, index: usize, name: Option<String>) -> usize
+    {
+        let Some(prefix) = &stream.prefix else
+        {
+            if let Some(name) = name
+            {
+                self.name_channel(index, name);
+            }
+            return index;
+        };
+
+        let next = self.channels;
+        let channel = *stream.channels.entry(index).or_insert(next);
+        if channel == next
+        {
+            self.channels += 1;
+        }
+
+        match name {
+            Some(name) => self.name_channel(channel, prefix.to_string() + "/" + &name),
+            None if channel == next => self.name_channel(channel, prefix.to_string() + "/" + &index.to_string()),
+            None => {}
         }
+        channel
     }
 
-    fn send_mean(&mut self)
+    fn push_record(&mut self, stream: &mut Stream, mut record: Record)
     {
-        let time = self.time_start.elapsed().as_millis() as f64 / 1000.0;
-        self.tx.send(('t'.to_string(),time)).unwrap();
+        self.stats.valid += 1;
+
+        // Frames without a device time are stamped with the time they were received.
+        let host_time = self.host_time();
+        record.timestamp = Some(match record.timestamp {
+            Some(timestamp) => timestamp + *stream.device_start.get_or_insert(host_time),
+            None => host_time,
+        });
 
-        for i in 0..self.xyz.len()
+        if stream.prefix.is_none()
         {
-            if self.xyz[&(i as i64)].len() == 0
+            self.frames.push(record);
+            return;
+        }
+
+        let mut values = Vec::new();
+        for (index, value) in record.values.into_iter().enumerate()
+        {
+            let Some(value) = value else
             {
                 continue;
+            };
+
+            let index = self.channel(stream, index, None);
+            if values.len() <= index
+            {
+                values.resize(index + 1, None);
             }
+            values[index] = Some(value);
+        }
 
-            let mut mean:f64 = 0.0;
-            let len:usize = self.xyz[&(i as i64)].len().try_into().unwrap();
+        self.frames.push(Record { timestamp: record.timestamp, values });
+    }
 
-            for j in &self.xyz[&(i as i64)]
-            {
-                mean += j;
+    fn read_binary(&mut self, stream: &mut Stream, bytes: &[u8])
+    {
+        let Some(decoder) = &mut stream.decoder else
+        {
+            return;
+        };
+
+        let records = decoder.push(bytes);
+        self.stats.corrupt += decoder.take_corrupt();
+
+        for (packet, record) in records
+        {
+            self.terminal.push(TerminalLine { timestamp: self.host_time(), data: packet, failed: record.is_none() });
+            match record {
+                Some(record) => self.push_record(stream, record),
+                None => self.stats.dropped += 1,
             }
+        }
+    }
+
+    // Decodes the received bytes as UTF-8, keeping a character split between reads for the next one.
+    // Bytes that are not valid text, like line noise, are replaced with `�` and counted.
+    fn decode(&mut self, stream: &mut Stream, serial_buf: &[u8])
+    {
+        let mut bytes = std::mem::take(&mut stream.undecoded);
+        bytes.extend(serial_buf.iter().filter(|&&x| x != 0));
+
+        let mut rest = &bytes[..];
+        loop
+        {
+            match str::from_utf8(rest) {
+                Ok(text) => {
+                    stream.sparkle_heart += text;
+                    return;
+                }
+                Err(error) => {
+                    let (valid, after) = rest.split_at(error.valid_up_to());
+                    stream.sparkle_heart += str::from_utf8(valid).unwrap_or_default();
+
+                    let Some(len) = error.error_len() else
+                    {
+                        stream.undecoded = after.to_vec();
+                        return;
+                    };
 
-            self.tx.send(('y'.to_string()+&i.to_string(), mean/len as f64)).unwrap();
-            self.xyz.insert(i as i64, Vec::new());
+                    stream.sparkle_heart.push(char::REPLACEMENT_CHARACTER);
+                    self.stats.invalid += len as u64;
+                    rest = &after[len..];
+                }
+            }
         }
     }
 
-    fn send_all(&mut self)
+    fn read_text(&mut self, stream: &mut Stream, serial_buf: Vec<u8>)
     {
+        self.decode(stream, &serial_buf);
 
-        let time = self.time_start.elapsed().as_millis() as f64 / 1000.0 + (self.time as f64 / 1000.0);
+        let terminator = self.settings.terminator;
+        let terminator_text = terminator.text(self.settings.terminator_byte);
+        let sparkle_heart_split: Vec<String> =  stream.sparkle_heart.split(&terminator_text).map(|s| s.to_string()).collect();
+        let num_n:usize = sparkle_heart_split.len();
 
-        for i in 0..self.xyz.len()
+        // The terminal shows the bytes of each line as they came, the terminator is ASCII so they split the same way.
+        stream.raw.extend(serial_buf);
+        let raw_lines = split_lines(&mut stream.raw, terminator_text.as_bytes());
+
+        for (line, raw) in sparkle_heart_split[..num_n-1].iter().zip(raw_lines)
         {
-            if self.xyz[&(i as i64)].len() == 0
+            let line = terminator.line(line);
+            let frame = stream.parser.parse_line(line);
+
+            for (index, name) in stream.parser.take_new_labels()
             {
-                continue;
+                self.channel(stream, index, Some(name));
             }
 
-            let max = self.xyz[&(i as i64)].len() as f64;
+            let corrupt = stream.parser.take_corrupt();
+            self.stats.corrupt += corrupt;
+            self.stats.dropped += corrupt;
 
-            for j in 0..self.xyz[&(i as i64)].len()
+            let logged = frame.is_err() && self.settings.text_lines == TextLines::Log;
+            if !line.is_empty()
             {
-                let val = &time - (self.time as f64 / 1000.0) * (&max - j as f64) / max;
-                self.tx.send(('t'.to_string(), val)).unwrap();
-                self.tx.send(('y'.to_string()+&i.to_string(), self.xyz[&(i as i64)][j as usize])).unwrap();
+                self.terminal.push(TerminalLine { timestamp: self.host_time(), data: terminator.raw_line(&raw).to_vec(), failed: stream.parser.failed() || logged || line.contains(char::REPLACEMENT_CHARACTER) });
+            }
+
+            match frame {
+                Ok(Some(frame)) => self.push_record(stream, frame),
+                Ok(None) => (),
+                Err(warning) => match self.settings.text_lines {
+                    TextLines::Ignore => (),
+                    TextLines::Log => self.send(Event::Warning { source_id: self.source_id, text: warning }),
+                    TextLines::Marker => self.marker(line.trim()),
+                },
             }
-            self.xyz.insert(i as i64, Vec::new());
         }
+
+        stream.sparkle_heart = sparkle_heart_split[num_n-1].clone();
     }
 
-    pub fn run_tx(&self) -> mpsc::Sender<bool>
+    // Ends the column frames of the senders that sent nothing for the gap.
+    fn end_gaps(&mut self)
     {
-        return self.run_rtx.0.clone();
+        let gap = Duration::from_secs_f64(self.settings.frame_gap.max(0.0));
+        let mut streams = std::mem::take(&mut self.streams);
+        for stream in streams.values_mut()
+        {
+            if stream.received.elapsed() < gap
+            {
+                continue;
+            }
+            if let Some(record) = stream.parser.end_frame()
+            {
+                self.push_record(stream, record);
+            }
+        }
+        self.streams = streams;
     }
 
-    pub fn read(&mut self, set: u32)
+    // Sends the frames, lines and counts received since the last call to the display.
+    fn flush(&mut self)
     {
-        let port = serialport::new(&self.name_port, self.speed)
-            .timeout(Duration::from_millis(&self.time/2))
-            .open();
-
-        if port.is_err()
+        if !self.frames.is_empty()
         {
-            let _ = &self.error_f("Failed to open port".to_string());
-            return;
+            // Channels taken the same way go in one frame.
+            let takes: Vec<Take> = (0..self.channels()).map(|index| self.take(index)).collect();
+            for take in Take::ALL.into_iter().filter(|take| takes.contains(take))
+            {
+                match take {
+                    Take::All => self.send_all(&takes),
+                    take => self.send_taken(take, &takes),
+                }
+            }
+            self.frames.clear();
         }
 
-        let mut port = port.unwrap();
+        if !self.terminal.is_empty()
+        {
+            let lines = std::mem::take(&mut self.terminal);
+            self.send(Event::Terminal { source_id: self.source_id, lines });
+        }
 
-        thread::sleep(Duration::from_secs(2));
-        let _ = port.clear(serialport::ClearBuffer::Input);
-        thread::sleep(Duration::from_secs(1));
+        if self.stats != self.stats_sent
+        {
+            self.stats_sent = self.stats.clone();
+            self.send(Event::Stats { source_id: self.source_id, stats: self.stats.clone() });
+        }
+    }
 
-        let mut sparkle_heart = "".to_string();
-        let mut j = 0;
-        let mut y = 0;
+    fn marker(&self, text: &str)
+    {
+        self.send(Event::Marker { source_id: self.source_id, timestamp: self.host_time(), text: text.to_string() });
+    }
 
-        self.time_start = Instant::now();
-        self.n = 0;
-        let mut iiii = 1;
+    // Waits for the lost port to come back, None if reading was stopped meanwhile.
+    fn reconnect(&mut self, usb: &Option<UsbId>) -> Option<Box<dyn Source>>
+    {
+        self.flush();
+        self.marker("Disconnected");
+        self.send(Event::Status { source_id: self.source_id, text: "Waiting for ".to_string() + &self.settings.name_port });
 
         loop
         {
-            let bytes = port.bytes_to_read();
-            if bytes.is_err()
+            for command in self.command_rtx.1.try_iter()
             {
-                let _ = &self.error_f("No signal".to_string());
-                return;
+                match command {
+                    Command::Stop => return None,
+                    Command::Take { channel, take } => _ = self.settings.channel_takes.insert(channel, take),
+                    // Sending and resetting need the port, which is lost.
+                    Command::Reset | Command::Send { .. } => (),
+                }
             }
 
-            let mut serial_buf: Vec<u8> = vec![0; port.bytes_to_read().unwrap() as usize];
+            thread::sleep(Duration::from_millis(RECONNECT_POLL));
 
-            let _ = port.read(serial_buf.as_mut_slice());
+            let name = match (self.settings.reconnect, usb) {
+                (Reconnect::Usb, Some(usb)) => match usb.find_port() {
+                    Some(name) => name,
+                    None => continue,
+                },
+                _ => self.settings.name_port.to_string(),
+            };
 
-            serial_buf.retain(|&x| x != 0);
-            let a = serial_buf.to_vec().clone();
+            let Ok(port) = Target::parse(&name).open(&self.settings) else
+            {
+                continue;
+            };
 
-            let err = str::from_utf8(&a);
-            if err.is_err()
+            for stream in self.streams.values_mut()
             {
-                let _ = &self.error_f("Incorrect received data".to_string());
+                stream.resync();
+            }
+
+            self.marker("Reconnected");
+            self.send(Event::Status { source_id: self.source_id, text: "Port ".to_string() + &name + " opened" });
+            return Some(port);
+        }
+    }
+
+    pub fn command_tx(&self) -> mpsc::Sender<Command>
+    {
+        self.command_rtx.0.clone()
+    }
+
+    pub fn read(&mut self)
+    {
+        // Checks the settings before the port is opened.
+        let stream = match Stream::new(&self.settings, None) {
+            Ok(stream) => stream,
+            Err(error) => {
+                self.send(Event::Error { source_id: self.source_id, text: error });
+                return;
+            }
+        };
+
+        let target = Target::parse(&self.settings.name_port);
+        let usb = match &target {
+            Target::Serial(name) => UsbId::of_port(name),
+            _ => None,
+        };
+
+        let mut port = match target.open(&self.settings) {
+            Ok(port) => port,
+            Err(error) => {
+                self.send(Event::Error { source_id: self.source_id, text: error });
                 return;
             }
+        };
+
+        self.send(Event::Status { source_id: self.source_id, text: "Port ".to_string() + &self.settings.name_port + " opened" });
 
-            sparkle_heart += err.unwrap();
+        let mut stream = stream;
+        let settings = port.settings(&self.settings);
+        if settings != self.settings
+        {
+            self.settings = settings;
+            stream = match Stream::new(&self.settings, None) {
+                Ok(stream) => stream,
+                Err(error) => {
+                    self.send(Event::Error { source_id: self.source_id, text: error });
+                    return;
+                }
+            };
+        }
 
-            let sparkle_heart_split: Vec<String> =  sparkle_heart.split("\r\n").map(|s| s.to_string()).collect();
+        if port.starts_at_frame()
+        {
+            stream.sync();
+        }
 
-            let num_n:usize = sparkle_heart_split.len();
-            if num_n < 2
+        // Channels of senders kept apart are named once each sender appears.
+        let group = port.datagrams() && self.settings.group_senders;
+        if !group
+        {
+            if let Some(decoder) = &stream.decoder
             {
-                continue;
+                for (index, name) in decoder.names()
+                {
+                    self.name_channel(index, name);
+                }
             }
+            self.streams.insert("".to_string(), stream);
+        }
+
+        let mut updated = Instant::now();
 
-            for i in iiii*num_n..num_n-1
+        loop
+        {
+            let chunks = match port.read_from() {
+                Ok(chunks) => chunks,
+                Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                    self.flush();
+                    self.send(Event::Status { source_id: self.source_id, text: error.to_string() });
+                    return;
+                }
+                Err(_) if self.settings.reconnect != Reconnect::Off => {
+                    match self.reconnect(&usb) {
+                        Some(reopened) => port = reopened,
+                        None => return,
+                    }
+                    continue;
+                }
+                Err(_) => {
+                    self.send(Event::Error { source_id: self.source_id, text: "No signal".to_string() });
+                    return;
+                }
+            };
+
+            if self.settings.frame_separator == FrameSeparator::Gap
+            {
+                self.end_gaps();
+            }
+
+            for (sender, mut serial_buf) in chunks
             {
-                if  sparkle_heart_split[i] == ""
+                let key = if group { sender.to_string() } else { "".to_string() };
+                let received = !serial_buf.is_empty();
+
+                let mut stream = match self.streams.remove(&key) {
+                    Some(stream) => stream,
+                    None => {
+                        let Ok(mut stream) = Stream::new(&self.settings, Some(sender)) else
+                        {
+                            continue;
+                        };
+                        if port.starts_at_frame()
+                        {
+                            stream.sync();
+                        }
+
+                        if let Some(decoder) = &stream.decoder
+                        {
+                            for (index, name) in decoder.names()
+                            {
+                                self.channel(&mut stream, index, Some(name));
+                            }
+                        }
+                        stream
+                    }
+                };
+
+                if stream.decoder.is_some()
                 {
-                    y += 1;
-                    if y > 0
+                    // A datagram holds whole packets, so its end also ends a packet.
+                    if port.datagrams()
                     {
-                        y = 0;
-                        j = 0;
+                        serial_buf.push(stream.decoder.as_ref().unwrap().delimiter());
                     }
+                    self.read_binary(&mut stream, &serial_buf);
                 }
                 else
                 {
-
-                    let number_port = sparkle_heart_split[i].parse::<f64>();
-
-                    if number_port.is_err()
+                    // A datagram holds whole lines, and in the column format a whole frame.
+                    let terminator = self.settings.terminator.text(self.settings.terminator_byte);
+                    if port.datagrams() && !serial_buf.ends_with(terminator.as_bytes())
                     {
-                        let _ = &self.error_f("Warning! Incorrect received data".to_string());
-                        continue;
+                        serial_buf.extend_from_slice(terminator.as_bytes());
                     }
 
-                    let number_port = number_port.unwrap();
+                    self.read_text(&mut stream, serial_buf);
 
-                    match self.xyz.get(&j) {
-                        Option::None => _ = self.xyz.insert(j as i64, vec![number_port]),
-                        _ => _ = self.xyz.get_mut(&j).map(|val| val.push(number_port)),
+                    if port.datagrams()
+                    {
+                        if let Some(record) = stream.parser.end_frame()
+                        {
+                            self.push_record(&mut stream, record);
+                        }
                     }
+                }
 
-                    j += 1;
+                if received
+                {
+                    stream.received = Instant::now();
                 }
 
+                self.streams.insert(key, stream);
             }
 
-
-            if self.xyz.len() != 0
+            if updated.elapsed() >= Duration::from_millis(self.time)
             {
-                match &set {
-                    1 => self.send_all(),
-                    2 => self.send_lost(),
-                    3 => self.send_mean(),
-                    _ => todo!()
-                }
+                updated = Instant::now();
+                self.flush();
             }
-            sparkle_heart = sparkle_heart_split[num_n-1].clone();
 
-            if &sparkle_heart == ""
-            {
-                y +=1;
-            }
-            for i in  self.run_rtx.1.try_iter()
+            for command in self.command_rtx.1.try_iter()
             {
-                if i == false
-                {
-                    return;
+                match command {
+                    Command::Stop => return,
+                    Command::Reset => {
+                        if port.pulse_reset().is_err()
+                        {
+                            self.send(Event::Warning { source_id: self.source_id, text: "Failed to reset the device".to_string() });
+                        }
+                    }
+                    Command::Take { channel, take } => _ = self.settings.channel_takes.insert(channel, take),
+                    Command::Send { data, text } => {
+                        match port.write(&data) {
+                            Ok(()) => self.marker(&text),
+                            Err(_) => self.send(Event::Warning { source_id: self.source_id, text: "Failed to send ".to_string() + &text }),
+                        }
+                    }
                 }
             }
-            iiii = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parser::{Format, Terminator};
 
-            thread::sleep(Duration::from_millis(self.time));
+    // Reads the chunks, each from a sender whose channels are kept apart or from "" for the whole source,
+    // and returns what was sent to the display.
+    fn receive(settings: &Settings, chunks: &[(&str, &[u8])]) -> Vec<Event>
+    {
+        let (tx, rx) = mpsc::channel();
+        let mut read_port = ReadPort::new(0, settings.clone(), Instant::now(), tx);
+
+        for &(sender, bytes) in chunks
+        {
+            let mut stream = match read_port.streams.remove(sender) {
+                Some(stream) => stream,
+                None => Stream::new(settings, Some(sender.to_string()).filter(|sender| !sender.is_empty())).unwrap(),
+            };
+            stream.sync();
+            read_port.read_text(&mut stream, bytes.to_vec());
+            read_port.streams.insert(sender.to_string(), stream);
         }
+        read_port.flush();
+        drop(read_port);
+        rx.try_iter().collect()
+    }
+
+    fn terminal(events: &[Event]) -> Vec<Vec<u8>>
+    {
+        events.iter().filter_map(|event| match event {
+            Event::Terminal { lines, .. } => Some(lines.iter().map(|line| line.data.clone())),
+            _ => None,
+        }).flatten().collect()
+    }
+
+    fn frames(events: &[Event]) -> Vec<Vec<Option<f64>>>
+    {
+        events.iter().filter_map(|event| match event {
+            Event::Frame(frame) => Some(frame.values.clone()),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn channel_takes()
+    {
+        // A channel taken its own way is left out of the frame of the source and sent in a frame of its own.
+        let settings = Settings { format: Format::Line, send: Take::Mean, channel_takes: BTreeMap::from([("1".to_string(), Take::Max)]), ..Settings::default() };
+        let events = receive(&settings, &[("", b"1,10\n3,20\n")]);
+        assert_eq!(frames(&events), [vec![Some(2.0), None], vec![None, Some(20.0)]]);
+
+        // The take of a named channel is found by its name.
+        let settings = Settings { format: Format::Labeled, send: Take::Mean, channel_takes: BTreeMap::from([("b".to_string(), Take::Min)]), ..Settings::default() };
+        let events = receive(&settings, &[("", b"a:1 b:5\na:3 b:4\n")]);
+        assert_eq!(frames(&events), [vec![Some(2.0), None], vec![None, Some(4.0)]]);
+    }
+
+    #[test]
+    fn grouped_senders()
+    {
+        // Senders kept apart get channels of their own, also for the same name.
+        let settings = Settings { format: Format::Labeled, send: Take::All, ..Settings::default() };
+        let events = receive(&settings, &[("a", b"y:1\n"), ("b", b"y:2\n"), ("a", b"y:3\n")]);
+        let channels: Vec<(usize, String)> = events.iter().filter_map(|event| match event {
+            Event::Channel { index, name, .. } => Some((*index, name.clone())),
+            _ => None,
+        }).collect();
+        assert_eq!(channels, [(0, "a/y".to_string()), (1, "b/y".to_string())]);
+        assert_eq!(frames(&events), [vec![Some(1.0)], vec![None, Some(2.0)], vec![Some(3.0)]]);
+    }
+
+    #[test]
+    fn terminal_bytes()
+    {
+        // Lines keep their bytes as they came, also when they are split between reads.
+        let settings = Settings { format: Format::Line, ..Settings::default() };
+        let events = receive(&settings, &[("", b"1,2\r\n3,"), ("", b"4\nx\0\xFFy\n")]);
+        assert_eq!(terminal(&events), [b"1,2".to_vec(), b"3,4".to_vec(), b"x\0\xFFy".to_vec()]);
+
+        let settings = Settings { format: Format::Line, terminator: Terminator::CrLf, ..Settings::default() };
+        let events = receive(&settings, &[("", b"1\r"), ("", b"\n2\n\r\n")]);
+        assert_eq!(terminal(&events), [b"1".to_vec(), b"2\n".to_vec()]);
+    }
+
+    #[test]
+    fn decode()
+    {
+        // A character split between reads is kept for the next one, an invalid byte is replaced and counted.
+        let settings = Settings::default();
+        let (tx, _rx) = mpsc::channel();
+        let mut read_port = ReadPort::new(0, settings.clone(), Instant::now(), tx);
+        let mut stream = Stream::new(&settings, None).unwrap();
+
+        read_port.decode(&mut stream, b"a\xC3");
+        assert_eq!(stream.sparkle_heart, "a");
+        read_port.decode(&mut stream, b"\xA9b\xFFc");
+        assert_eq!(stream.sparkle_heart, "aéb�c");
+        assert_eq!(read_port.stats.invalid, 1);
     }
 }
@@ -0,0 +1,217 @@
+// Plays back a file saved with `Save`, or a raw log of received bytes.
+
+use std::fs;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::checksum::Checksum;
+use crate::clock::TimeUnit;
+use crate::parser::{Delimiter, Format};
+use crate::settings::Settings;
+use crate::source::{Source, READ_WAIT};
+
+// First column of the header written by `Save`.
+const CAPTURE_HEADER: &str = "t,s";
+// Rows sent by one read when playing as fast as possible.
+const FASTEST_ROWS: usize = 10_000;
+const FASTEST_BYTES: usize = 65_536;
+
+pub struct ReplaySource
+{
+    // Rows of a saved capture as (time, line), empty for a raw log.
+    rows: Vec<(f64, Vec<u8>)>,
+    raw: Vec<u8>,
+    position: usize,
+    // Playback speed, 0 to play as fast as possible.
+    speed: f64,
+    // Bytes per second of a raw log at normal speed.
+    byte_rate: f64,
+    time_start: Instant,
+}
+
+impl ReplaySource
+{
+    pub fn open(path: &str, settings: &Settings) -> Result<ReplaySource, String>
+    {
+        let data = fs::read(path).map_err(|_| "Failed to open file ".to_string() + path)?;
+
+        let mut replay = ReplaySource
+        {
+            rows: Vec::new(),
+            raw: Vec::new(),
+            position: 0,
+            speed: settings.replay_speed,
+            // A serial frame of a byte takes ten bits.
+            byte_rate: (settings.speed as f64 / 10.0).max(1.0),
+            time_start: Instant::now(),
+        };
+
+        match std::str::from_utf8(&data).ok().filter(|text| text.starts_with(CAPTURE_HEADER)) {
+            Some(text) => replay.rows = capture_rows(text),
+            None => replay.raw = data,
+        }
+        Ok(replay)
+    }
+
+    fn is_capture(&self) -> bool
+    {
+        !self.rows.is_empty()
+    }
+
+    // Bytes whose time has come.
+    fn due(&mut self) -> io::Result<Vec<u8>>
+    {
+        let mut data = Vec::new();
+
+        if self.is_capture()
+        {
+            let start = self.rows[0].0;
+            let end = if self.speed <= 0.0 { (self.position + FASTEST_ROWS).min(self.rows.len()) } else { self.rows.len() };
+
+            while self.position < end && (self.speed <= 0.0 || self.rows[self.position].0 - start <= self.elapsed())
+            {
+                data.extend_from_slice(&self.rows[self.position].1);
+                self.position += 1;
+            }
+
+            if data.is_empty() && self.position >= self.rows.len()
+            {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Replay finished"));
+            }
+            return Ok(data);
+        }
+
+        let end = if self.speed <= 0.0 { self.position + FASTEST_BYTES } else { (self.elapsed() * self.byte_rate) as usize };
+        let end = end.min(self.raw.len());
+
+        if self.position >= self.raw.len()
+        {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Replay finished"));
+        }
+
+        if end > self.position
+        {
+            data.extend_from_slice(&self.raw[self.position..end]);
+            self.position = end;
+        }
+        Ok(data)
+    }
+
+    // Time since the start of the playback in the time of the file.
+    fn elapsed(&self) -> f64
+    {
+        self.time_start.elapsed().as_secs_f64() * self.speed
+    }
+}
+
+// Turns the rows of a saved capture into `name:value` lines, the time named `t`.
+fn capture_rows(text: &str) -> Vec<(f64, Vec<u8>)>
+{
+    let mut lines = text.lines();
+    let names: Vec<&str> = lines.next().unwrap_or("").split('\t').skip(1).collect();
+    let mut rows = Vec::new();
+
+    for line in lines
+    {
+        let mut values = line.split('\t');
+        let Some(Ok(time)) = values.next().map(|time| time.trim().parse::<f64>()) else
+        {
+            continue;
+        };
+
+        let mut row = "t:".to_string() + &time.to_string();
+        for (name, value) in names.iter().zip(values)
+        {
+            if !value.trim().is_empty()
+            {
+                row += &("\t".to_string() + name + ":" + value.trim());
+            }
+        }
+        row += "\n";
+        rows.push((time, row.into_bytes()));
+    }
+    rows
+}
+
+impl Source for ReplaySource
+{
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        let data = self.due()?;
+        if !data.is_empty()
+        {
+            return Ok(data);
+        }
+
+        thread::sleep(Duration::from_millis(READ_WAIT));
+        self.due()
+    }
+
+    fn starts_at_frame(&self) -> bool
+    {
+        self.is_capture()
+    }
+
+    fn settings(&self, settings: &Settings) -> Settings
+    {
+        if !self.is_capture()
+        {
+            return settings.clone();
+        }
+
+        Settings
+        {
+            format: Format::Labeled,
+            delimiter: Delimiter::Tab,
+            time_field: "t".to_string(),
+            time_unit: TimeUnit::S,
+            time_wrap: 0,
+            checksum: Checksum::None,
+            ..settings.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::parser::{Parser, Record};
+
+    #[test]
+    fn capture()
+    {
+        // Empty cells are values the channel did not receive at that time, names may hold `:` and `/`.
+        let text = "t,s\tdev/x:y\tb\n1.5\t1\t\n2.0\t\t4\nnot a row\n2.5\t3\t5\n";
+        let replay = ReplaySource
+        {
+            rows: capture_rows(text),
+            raw: Vec::new(),
+            position: 0,
+            speed: 1.0,
+            byte_rate: 1.0,
+            time_start: Instant::now(),
+        };
+        assert_eq!(replay.rows.iter().map(|(time, _)| *time).collect::<Vec<_>>(), [1.5, 2.0, 2.5]);
+
+        let mut parser = Parser::new(&replay.settings(&Settings::default()));
+        parser.sync();
+
+        let mut records = Vec::new();
+        let mut labels = Vec::new();
+        for (_, row) in &replay.rows
+        {
+            let line = std::str::from_utf8(row).unwrap().strip_suffix('\n').unwrap();
+            records.push(parser.parse_line(line).unwrap().unwrap());
+            labels.extend(parser.take_new_labels());
+        }
+
+        assert_eq!(labels, [(0, "dev/x:y".to_string()), (1, "b".to_string())]);
+        assert_eq!(records, [
+            Record { timestamp: Some(0.0), values: vec![Some(1.0)] },
+            Record { timestamp: Some(0.5), values: vec![None, Some(4.0)] },
+            Record { timestamp: Some(1.0), values: vec![Some(3.0), Some(5.0)] },
+        ]);
+    }
+}
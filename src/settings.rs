@@ -0,0 +1,390 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+
+use crate::checksum::Checksum;
+use crate::clock::TimeUnit;
+use crate::console::{LineEnding, Macro};
+use crate::parser::{Delimiter, Format, FrameSeparator, Terminator, TextLines};
+use crate::simulator::Waveform;
+use crate::source::{FlowControl, Parity, Reconnect};
+use crate::take::Take;
+
+const NAME_FILE_SETTINGS: &str = "./settings.log";
+const SOURCE_HEADER: &str = "[source]";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings
+{
+    // Prefix of the channel names when several sources are read, the port name if empty.
+    pub label: String,
+    pub name_port: String,
+    pub speed: u32,
+    // Line settings of a serial port, like 8N1.
+    pub data_bits: u8,
+    pub parity: Parity,
+    pub stop_bits: u8,
+    pub flow_control: FlowControl,
+    // States of DTR and RTS set on open, and seconds to wait for the board to reset before reading.
+    pub dtr: bool,
+    pub rts: bool,
+    pub settle: f64,
+    // Keeps the session when the port is lost and opens it again once it is back.
+    pub reconnect: Reconnect,
+    pub time: f64,
+    pub send: Take,
+    // Channels taken their own way, by name, or by index when they are not named.
+    pub channel_takes: BTreeMap<String, Take>,
+    pub format: Format,
+    pub delimiter: Delimiter,
+    pub text_lines: TextLines,
+    // End of a line, with the byte of a custom one.
+    pub terminator: Terminator,
+    pub terminator_byte: u8,
+    // End of a column frame, with the sentinel text, number of values or seconds without data.
+    pub frame_separator: FrameSeparator,
+    pub frame_sentinel: String,
+    pub frame_count: usize,
+    pub frame_gap: f64,
+    // Field holding the device time, by index or name, empty to use the host time.
+    pub time_field: String,
+    pub time_unit: TimeUnit,
+    pub time_wrap: u32,
+    pub layout: String,
+    pub big_endian: bool,
+    pub checksum: Checksum,
+    // Gives every sender of datagrams its own channels.
+    pub group_senders: bool,
+    // Playback speed of a file, 0 to play it as fast as possible.
+    pub replay_speed: f64,
+    // Signal of the simulated device, made of samples of all channels sent `sim_rate` times a second.
+    pub sim_waveform: Waveform,
+    pub sim_channels: usize,
+    pub sim_rate: f64,
+    pub sim_frequency: f64,
+    // Sending commands to the device: line ending added to text, commands typed as hex and saved commands.
+    pub line_ending: LineEnding,
+    pub send_hex: bool,
+    pub macros: Vec<Macro>,
+}
+
+impl Default for Settings
+{
+    fn default() -> Self
+    {
+        Settings
+        {
+            label: "".to_string(),
+            name_port: "".to_string(),
+            speed: 9_600,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+            flow_control: FlowControl::None,
+            dtr: true,
+            rts: true,
+            settle: 3.0,
+            reconnect: Reconnect::Off,
+            time: 1.0,
+            send: Take::All,
+            channel_takes: BTreeMap::new(),
+            format: Format::Column,
+            delimiter: Delimiter::Auto,
+            text_lines: TextLines::Log,
+            terminator: Terminator::Lf,
+            terminator_byte: b';',
+            frame_separator: FrameSeparator::Blank,
+            frame_sentinel: "---".to_string(),
+            frame_count: 3,
+            frame_gap: 2.0,
+            time_field: "".to_string(),
+            time_unit: TimeUnit::S,
+            time_wrap: 0,
+            layout: "f32".to_string(),
+            big_endian: false,
+            checksum: Checksum::None,
+            group_senders: false,
+            replay_speed: 1.0,
+            sim_waveform: Waveform::Sine,
+            sim_channels: 3,
+            sim_rate: 100.0,
+            sim_frequency: 1.0,
+            line_ending: LineEnding::Lf,
+            send_hex: false,
+            macros: Vec::new(),
+        }
+    }
+}
+
+impl Settings
+{
+    // Settings of every source, each source after the first starts with a `[source]` line.
+    pub fn load() -> Vec<Settings>
+    {
+        let Ok(mut file) = File::open(NAME_FILE_SETTINGS) else
+        {
+            return vec![Settings::default()];
+        };
+
+        let mut contents = String::new();
+        let _ = file.read_to_string(&mut contents);
+        Settings::parse(&contents)
+    }
+
+    pub fn save(sources: &[Settings])
+    {
+        let Ok(mut file) = File::create(NAME_FILE_SETTINGS) else
+        {
+            return;
+        };
+        let _ = file.write_all(Settings::text(sources).as_bytes());
+    }
+
+    // Settings of every source from the contents of the settings file.
+    fn parse(contents: &str) -> Vec<Settings>
+    {
+        let mut sources = vec![Settings::default()];
+
+        // Files written before settings had names hold port, speed, time and send on separate lines.
+        if !contents.contains('=')
+        {
+            let s = contents.split('\n').collect::<Vec<_>>();
+            sources[0].set("port", s[0]);
+            for (key, value) in ["speed", "time", "send"].iter().zip(s.iter().skip(1))
+            {
+                sources[0].set(key, value);
+            }
+            return sources;
+        }
+
+        for line in contents.lines()
+        {
+            if line.trim() == SOURCE_HEADER
+            {
+                sources.push(Settings::default());
+            }
+            else if let Some((key, value)) = line.split_once('=')
+            {
+                sources.last_mut().unwrap().set(key.trim(), value.trim());
+            }
+        }
+        sources
+    }
+
+    // Contents of the settings file for every source.
+    fn text(sources: &[Settings]) -> String
+    {
+        let mut text = String::new();
+
+        for (i, settings) in sources.iter().enumerate()
+        {
+            if i > 0
+            {
+                text += &(SOURCE_HEADER.to_string() + "\n");
+            }
+            for (key, value) in settings.values()
+            {
+                text += &(key.to_string() + "=" + &value + "\n");
+            }
+        }
+        text
+    }
+
+    // Line settings written like `8N1`.
+    pub fn line(&self) -> String
+    {
+        self.data_bits.to_string() + self.parity.letter() + &self.stop_bits.to_string()
+    }
+
+    // Prefix of the channel names of this source.
+    pub fn prefix(&self) -> String
+    {
+        if self.label.trim().is_empty()
+        {
+            return self.name_port.to_string();
+        }
+        self.label.trim().to_string()
+    }
+
+    fn values(&self) -> Vec<(&'static str, String)>
+    {
+        let mut values = vec![
+            ("label", self.label.to_string()),
+            ("port", self.name_port.to_string()),
+            ("speed", self.speed.to_string()),
+            ("data_bits", self.data_bits.to_string()),
+            ("parity", self.parity.name().to_string()),
+            ("stop_bits", self.stop_bits.to_string()),
+            ("flow_control", self.flow_control.name().to_string()),
+            ("dtr", self.dtr.to_string()),
+            ("rts", self.rts.to_string()),
+            ("settle", self.settle.to_string()),
+            ("reconnect", self.reconnect.name().to_string()),
+            ("time", self.time.to_string()),
+            ("send", self.send.name().to_string()),
+            ("format", self.format.name().to_string()),
+            ("delimiter", self.delimiter.name().to_string()),
+            ("text_lines", self.text_lines.name().to_string()),
+            ("terminator", self.terminator.name().to_string()),
+            ("terminator_byte", self.terminator_byte.to_string()),
+            ("frame_separator", self.frame_separator.name().to_string()),
+            ("frame_sentinel", self.frame_sentinel.to_string()),
+            ("frame_count", self.frame_count.to_string()),
+            ("frame_gap", self.frame_gap.to_string()),
+            ("time_field", self.time_field.to_string()),
+            ("time_unit", self.time_unit.name().to_string()),
+            ("time_wrap", self.time_wrap.to_string()),
+            ("layout", self.layout.to_string()),
+            ("big_endian", self.big_endian.to_string()),
+            ("checksum", self.checksum.name().to_string()),
+            ("group_senders", self.group_senders.to_string()),
+            ("replay_speed", self.replay_speed.to_string()),
+            ("sim_waveform", self.sim_waveform.name().to_string()),
+            ("sim_channels", self.sim_channels.to_string()),
+            ("sim_rate", self.sim_rate.to_string()),
+            ("sim_frequency", self.sim_frequency.to_string()),
+            ("line_ending", self.line_ending.name().to_string()),
+            ("send_hex", self.send_hex.to_string()),
+        ];
+        // Every channel taken its own way and every macro is a line of its own.
+        values.extend(self.channel_takes.iter().map(|(channel, take)| ("channel_take", channel.to_string() + "|" + take.name())));
+        values.extend(self.macros.iter().map(|item| ("macro", item.value())));
+        values
+    }
+
+    // Unknown keys and values that fail to parse keep their defaults.
+    fn set(&mut self, key: &str, value: &str)
+    {
+        match key {
+            "label" => self.label = value.to_string(),
+            "port" => self.name_port = value.to_string(),
+            "speed" => self.speed = value.parse().unwrap_or(self.speed),
+            "data_bits" => self.data_bits = value.parse().unwrap_or(self.data_bits),
+            "parity" => self.parity = Parity::from_name(value).unwrap_or(self.parity),
+            "stop_bits" => self.stop_bits = value.parse().unwrap_or(self.stop_bits),
+            "flow_control" => self.flow_control = FlowControl::from_name(value).unwrap_or(self.flow_control),
+            "dtr" => self.dtr = value.parse().unwrap_or(self.dtr),
+            "rts" => self.rts = value.parse().unwrap_or(self.rts),
+            "settle" => self.settle = value.parse().unwrap_or(self.settle),
+            "reconnect" => self.reconnect = Reconnect::from_name(value).unwrap_or(self.reconnect),
+            "time" => self.time = value.parse().unwrap_or(self.time),
+            // Files written before the modes had names hold 1, 2 or 3.
+            "send" => self.send = Take::from_name(value).or(match value { "1" => Some(Take::All), "2" => Some(Take::Lost), "3" => Some(Take::Mean), _ => None }).unwrap_or(self.send),
+            "format" => self.format = Format::from_name(value).unwrap_or(self.format),
+            "delimiter" => self.delimiter = Delimiter::from_name(value).unwrap_or(self.delimiter),
+            "text_lines" => self.text_lines = TextLines::from_name(value).unwrap_or(self.text_lines),
+            "terminator" => self.terminator = Terminator::from_name(value).unwrap_or(self.terminator),
+            "terminator_byte" => self.terminator_byte = value.parse().unwrap_or(self.terminator_byte),
+            "frame_separator" => self.frame_separator = FrameSeparator::from_name(value).unwrap_or(self.frame_separator),
+            "frame_sentinel" => self.frame_sentinel = value.to_string(),
+            "frame_count" => self.frame_count = value.parse().unwrap_or(self.frame_count),
+            "frame_gap" => self.frame_gap = value.parse().unwrap_or(self.frame_gap),
+            "time_field" => self.time_field = value.to_string(),
+            "time_unit" => self.time_unit = TimeUnit::from_name(value).unwrap_or(self.time_unit),
+            "time_wrap" => self.time_wrap = value.parse().unwrap_or(self.time_wrap),
+            "layout" => self.layout = value.to_string(),
+            "big_endian" => self.big_endian = value.parse().unwrap_or(self.big_endian),
+            "checksum" => self.checksum = Checksum::from_name(value).unwrap_or(self.checksum),
+            "group_senders" => self.group_senders = value.parse().unwrap_or(self.group_senders),
+            "replay_speed" => self.replay_speed = value.parse().unwrap_or(self.replay_speed),
+            "sim_waveform" => self.sim_waveform = Waveform::from_name(value).unwrap_or(self.sim_waveform),
+            "sim_channels" => self.sim_channels = value.parse().unwrap_or(self.sim_channels),
+            "sim_rate" => self.sim_rate = value.parse().unwrap_or(self.sim_rate),
+            "sim_frequency" => self.sim_frequency = value.parse().unwrap_or(self.sim_frequency),
+            "line_ending" => self.line_ending = LineEnding::from_name(value).unwrap_or(self.line_ending),
+            "send_hex" => self.send_hex = value.parse().unwrap_or(self.send_hex),
+            "channel_take" => {
+                // The name of the channel may hold a `|`, the way it is taken can not.
+                if let Some((channel, take)) = value.rsplit_once('|')
+                {
+                    if let Some(take) = Take::from_name(take)
+                    {
+                        self.channel_takes.insert(channel.to_string(), take);
+                    }
+                }
+            }
+            "macro" => self.macros.extend(Macro::parse(value)),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn round_trip()
+    {
+        let serial = Settings
+        {
+            name_port: "/dev/ttyUSB0".to_string(),
+            speed: 115_200,
+            data_bits: 7,
+            parity: Parity::Even,
+            stop_bits: 2,
+            flow_control: FlowControl::Hardware,
+            macros: vec![Macro { name: "reset".to_string(), command: "AT|Z".to_string() }],
+            ..Settings::default()
+        };
+        let sources = vec![serial, Settings { label: "board".to_string(), name_port: "tcp://127.0.0.1:5000".to_string(), ..Settings::default() }];
+
+        let text = Settings::text(&sources);
+        assert_eq!(text.lines().filter(|line| *line == SOURCE_HEADER).count(), 1);
+        assert_eq!(Settings::parse(&text), sources);
+    }
+
+    #[test]
+    fn line_ends()
+    {
+        let settings = Settings
+        {
+            terminator: Terminator::Custom,
+            terminator_byte: b'|',
+            frame_separator: FrameSeparator::Sentinel,
+            frame_sentinel: "--- end".to_string(),
+            frame_count: 8,
+            frame_gap: 0.25,
+            ..Settings::default()
+        };
+        assert_eq!(Settings::parse(&Settings::text(std::slice::from_ref(&settings))), [settings]);
+
+        // The byte of a custom terminator is a number.
+        let sources = Settings::parse("terminator=crlf\nterminator_byte=;\nframe_separator=gap\nframe_count=-1\n");
+        assert_eq!(sources, [Settings { terminator: Terminator::CrLf, frame_separator: FrameSeparator::Gap, ..Settings::default() }]);
+    }
+
+    #[test]
+    fn channel_takes()
+    {
+        // Names of channels may hold `|`, the take after the last one is read.
+        let settings = Settings
+        {
+            send: Take::Envelope,
+            channel_takes: BTreeMap::from([("0".to_string(), Take::Max), ("a|b".to_string(), Take::Rms), ("udp/x".to_string(), Take::Std)]),
+            ..Settings::default()
+        };
+        let text = Settings::text(std::slice::from_ref(&settings));
+        assert!(text.contains("channel_take=a|b|rms\n"));
+        assert_eq!(Settings::parse(&text), [settings]);
+
+        // A take without a known way is left out.
+        assert_eq!(Settings::parse("channel_take=x|fastest\nchannel_take=y\n"), [Settings::default()]);
+    }
+
+    #[test]
+    fn parse()
+    {
+        // Unknown keys and values that fail to parse keep their defaults.
+        let sources = Settings::parse("port = COM3\nparity=mark\ndata_bits=x\nflow_control=xon/xoff\ncolor=red\n[source]\nport=sim://\n");
+        assert_eq!(sources, [
+            Settings { name_port: "COM3".to_string(), flow_control: FlowControl::Software, ..Settings::default() },
+            Settings { name_port: "sim://".to_string(), ..Settings::default() },
+        ]);
+
+        // The file of the first versions holds port, speed, time and send on their own lines.
+        assert_eq!(Settings::parse("COM4\n115200\n0.5\n3"), [Settings { name_port: "COM4".to_string(), speed: 115_200, time: 0.5, send: Take::Mean, ..Settings::default() }]);
+        assert_eq!(Settings::parse(""), [Settings::default()]);
+    }
+}
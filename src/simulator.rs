@@ -0,0 +1,358 @@
+// Simulated device: generated waveforms written in the selected format.
+
+use std::f64::consts::PI;
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::binary::{FieldType, Layout};
+use crate::clock::TimeUnit;
+use crate::parser::{Delimiter, Format, FrameSeparator};
+use crate::settings::Settings;
+use crate::source::{Source, READ_WAIT};
+
+// Samples written by one read at most, so a slow reader does not get them all at once.
+const MAX_SAMPLES: u64 = 10_000;
+// Seconds a chirp takes to sweep from the frequency to ten times it.
+const CHIRP_TIME: f64 = 10.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Waveform
+{
+    Sine,
+    Square,
+    Saw,
+    Noise,
+    Steps,
+    Chirp,
+}
+
+impl Waveform
+{
+    pub const ALL: [Waveform; 6] = [Waveform::Sine, Waveform::Square, Waveform::Saw, Waveform::Noise, Waveform::Steps, Waveform::Chirp];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Waveform::Sine => "sine",
+            Waveform::Square => "square",
+            Waveform::Saw => "saw",
+            Waveform::Noise => "noise",
+            Waveform::Steps => "steps",
+            Waveform::Chirp => "chirp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Waveform>
+    {
+        Waveform::ALL.into_iter().find(|waveform| waveform.name() == name)
+    }
+
+    // Value from -1 to 1 at the time in seconds, of a wave at the frequency shifted by `shift` periods.
+    fn value(&self, time: f64, frequency: f64, shift: f64, noise: &mut u64) -> f64
+    {
+        let phase = time * frequency + shift;
+        match self {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => if phase.fract() < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Saw => 2.0 * phase.fract() - 1.0,
+            Waveform::Noise => {
+                // xorshift, so every run gives the same noise.
+                *noise ^= *noise << 13;
+                *noise ^= *noise >> 7;
+                *noise ^= *noise << 17;
+                (*noise >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            }
+            Waveform::Steps => (phase.floor().rem_euclid(5.0)) / 2.0 - 1.0,
+            Waveform::Chirp => {
+                // The frequency rises linearly to ten times it, so the phase grows with the square of the time.
+                let time = time.rem_euclid(CHIRP_TIME);
+                (2.0 * PI * (frequency * (time + 4.5 * time * time / CHIRP_TIME) + shift)).sin()
+            }
+        }
+    }
+}
+
+pub struct Simulator
+{
+    settings: Settings,
+    layout: Option<Layout>,
+    names: Vec<String>,
+    // Index of the value holding the device time, if it is sent by index.
+    time_index: Option<usize>,
+    noise: Vec<u64>,
+    sample: u64,
+    time_start: Instant,
+}
+
+impl Simulator
+{
+    pub fn new(settings: &Settings) -> Result<Simulator, String>
+    {
+        let layout = match settings.format.framing() {
+            Some(_) => Some(Layout::parse(&settings.layout, settings.big_endian)?),
+            None => None,
+        };
+
+        // Binary packets hold as many values as the layout has fields.
+        let channels = match &layout {
+            Some(layout) => layout.len(),
+            None => settings.sim_channels.max(1),
+        };
+
+        let mut names: Vec<String> = (0..channels).map(|i| settings.sim_waveform.name().to_string() + &i.to_string()).collect();
+        if let Some(layout) = &layout
+        {
+            for (index, name) in layout.names()
+            {
+                names[index] = name;
+            }
+        }
+
+        let time_field = settings.time_field.trim();
+        let time_index = match time_field.parse::<usize>() {
+            Ok(index) => Some(index),
+            Err(_) if layout.is_some() => names.iter().position(|name| name == time_field),
+            Err(_) => None,
+        };
+
+        Ok(Simulator
+        {
+            settings: settings.clone(),
+            layout,
+            names,
+            time_index,
+            noise: (0..channels as u64).map(|i| 0x9E37_79B9_7F4A_7C15 ^ (i + 1)).collect(),
+            sample: 0,
+            time_start: Instant::now(),
+        })
+    }
+
+    // Values of a sample, each channel shifted in phase from the previous one.
+    fn values(&mut self, time: f64) -> Vec<f64>
+    {
+        let channels = self.noise.len();
+        let mut values = Vec::with_capacity(channels);
+
+        for (i, noise) in self.noise.iter_mut().enumerate()
+        {
+            let shift = i as f64 / channels as f64;
+            values.push(self.settings.sim_waveform.value(time, self.settings.sim_frequency, shift, noise));
+        }
+        values
+    }
+
+    // Time as the device would send it, in the time unit and wrapped around like a counter.
+    fn device_time(&self, time: f64) -> f64
+    {
+        // Counters in milliseconds or microseconds are whole numbers, seconds are sent with their fraction.
+        let raw = match self.settings.time_unit {
+            TimeUnit::S => time,
+            unit => (time / unit.seconds()).round(),
+        };
+        match self.settings.time_wrap {
+            0 => raw,
+            wrap => raw.rem_euclid(2f64.powi(wrap as i32)),
+        }
+    }
+
+    fn encode(&self, time: f64, mut values: Vec<f64>) -> Vec<u8>
+    {
+        let time_field = self.settings.time_field.trim();
+        let checksum = self.settings.checksum;
+
+        if let (Some(layout), Some(framing)) = (&self.layout, self.settings.format.framing())
+        {
+            values = scale(layout, &values);
+            if let Some(index) = self.time_index.filter(|&index| index < values.len())
+            {
+                values[index] = self.device_time(time);
+            }
+
+            let mut payload = layout.encode(&values);
+            checksum.append_binary(&mut payload, layout.big_endian);
+            return framing.encode(&payload);
+        }
+
+        let mut fields: Vec<(String, f64)> = self.names.iter().cloned().zip(values).collect();
+        // Text formats other than labeled and JSON can only hold the time at an index.
+        if !time_field.is_empty() && (self.time_index.is_some() || matches!(self.settings.format, Format::Labeled | Format::Json))
+        {
+            let index = self.time_index.unwrap_or(0).min(fields.len());
+            fields.insert(index, (time_field.to_string(), self.device_time(time)));
+        }
+
+        let separator = match self.settings.delimiter {
+            Delimiter::Auto | Delimiter::Comma => ",",
+            Delimiter::Semicolon => ";",
+            Delimiter::Space => " ",
+            Delimiter::Tab => "\t",
+        };
+
+        let terminator = self.settings.terminator.text(self.settings.terminator_byte);
+        let text = match self.settings.format {
+            Format::Column => {
+                let mut text = String::new();
+                for (_, value) in &fields
+                {
+                    text += &(checksum.append_text(&value.to_string()) + &terminator);
+                }
+                // Frames of a fixed count or ended by a gap have nothing after them.
+                match self.settings.frame_separator {
+                    FrameSeparator::Blank => text += &terminator,
+                    FrameSeparator::Sentinel => text += &(self.settings.frame_sentinel.trim().to_string() + &terminator),
+                    FrameSeparator::Count | FrameSeparator::Gap => (),
+                }
+                return text.into_bytes();
+            }
+            Format::Labeled => fields.iter().map(|(name, value)| name.to_string() + ":" + &value.to_string()).collect::<Vec<_>>().join(separator),
+            Format::Json => {
+                let object: serde_json::Map<String, serde_json::Value> = fields.into_iter().map(|(name, value)| (name, serde_json::json!(value))).collect();
+                serde_json::Value::Object(object).to_string()
+            }
+            _ => fields.iter().map(|(_, value)| value.to_string()).collect::<Vec<_>>().join(separator),
+        };
+        (checksum.append_text(&text) + &terminator).into_bytes()
+    }
+}
+
+// Scales the values of integer fields from -1..1 to a range that fits the field, from 0 for unsigned ones.
+fn scale(layout: &Layout, values: &[f64]) -> Vec<f64>
+{
+    layout.fields.iter()
+        .flat_map(|field| std::iter::repeat_n(field.kind, field.count))
+        .zip(values)
+        .map(|(kind, &value)| match kind {
+            FieldType::F32 | FieldType::F64 => value,
+            FieldType::I8 => value * 100.0,
+            FieldType::U8 => (value + 1.0) * 100.0,
+            FieldType::U16 | FieldType::U32 | FieldType::U64 => (value + 1.0) * 10_000.0,
+            _ => value * 10_000.0,
+        })
+        .collect()
+}
+
+impl Source for Simulator
+{
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        let rate = self.settings.sim_rate.max(0.001);
+
+        // Waits for the next sample, `READ_WAIT` at most.
+        let wait = (self.sample + 1) as f64 / rate - self.time_start.elapsed().as_secs_f64();
+        if wait > 0.0
+        {
+            thread::sleep(Duration::from_secs_f64(wait.min(READ_WAIT as f64 / 1000.0)));
+        }
+
+        let due = (self.time_start.elapsed().as_secs_f64() * rate) as u64;
+        let end = due.min(self.sample + MAX_SAMPLES);
+
+        let mut data = Vec::new();
+        while self.sample < end
+        {
+            let time = self.sample as f64 / rate;
+            let values = self.values(time);
+            data.extend(self.encode(time, values));
+            self.sample += 1;
+        }
+        Ok(data)
+    }
+
+    fn starts_at_frame(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::binary::Decoder;
+    use crate::checksum::Checksum;
+    use crate::parser::{Parser, Record};
+
+    // A sine of 1 Hz on every channel, each shifted by a part of the period.
+    fn sine(time: f64, channels: usize) -> Vec<f64>
+    {
+        (0..channels).map(|i| (2.0 * PI * (time + i as f64 / channels as f64)).sin()).collect()
+    }
+
+    // Frames read back from what the simulator sends at the times.
+    fn parse_back(settings: &Settings, times: &[f64]) -> Vec<Record>
+    {
+        let mut simulator = Simulator::new(settings).unwrap();
+        let mut parser = Parser::new(settings);
+        parser.sync();
+
+        let mut records = Vec::new();
+        for &time in times
+        {
+            let values = simulator.values(time);
+            let text = String::from_utf8(simulator.encode(time, values)).unwrap();
+            for line in text.split_terminator('\n')
+            {
+                records.extend(parser.parse_line(line).unwrap());
+            }
+        }
+        records
+    }
+
+    #[test]
+    fn chirp()
+    {
+        // The sweep takes `CHIRP_TIME` seconds whatever the frequency.
+        let mut noise = 0;
+        for frequency in [0.5, 2.0]
+        {
+            let phase = |time: f64| frequency * (time + 4.5 * time * time / CHIRP_TIME);
+            assert_eq!(Waveform::Chirp.value(1.5, frequency, 0.0, &mut noise), (2.0 * PI * phase(1.5)).sin());
+            assert_eq!(Waveform::Chirp.value(CHIRP_TIME + 1.5, frequency, 0.0, &mut noise), Waveform::Chirp.value(1.5, frequency, 0.0, &mut noise));
+        }
+    }
+
+    #[test]
+    fn text_round_trip()
+    {
+        let record = |timestamp: Option<f64>, values: Vec<Option<f64>>| Record { timestamp, values };
+        let values = |time: f64| sine(time, 3).into_iter().map(Some).collect::<Vec<_>>();
+
+        // The device time is sent in milliseconds, the first frame is at 0.
+        let settings = Settings { format: Format::Line, time_field: "0".to_string(), time_unit: TimeUnit::Ms, checksum: Checksum::Xor, ..Settings::default() };
+        let with_time = |time: f64| [None].into_iter().chain(values(time)).collect::<Vec<_>>();
+        assert_eq!(parse_back(&settings, &[0.25, 0.26]), [record(Some(0.0), with_time(0.25)), record(Some(0.01), with_time(0.26))]);
+
+        for format in [Format::Labeled, Format::Json]
+        {
+            let settings = Settings { format, delimiter: Delimiter::Tab, time_field: "ms".to_string(), time_unit: TimeUnit::Ms, ..Settings::default() };
+            assert_eq!(parse_back(&settings, &[0.25, 0.26]), [record(Some(0.0), values(0.25)), record(Some(0.01), values(0.26))]);
+        }
+
+        let settings = Settings { format: Format::Column, frame_separator: FrameSeparator::Sentinel, frame_sentinel: "---".to_string(), ..Settings::default() };
+        assert_eq!(parse_back(&settings, &[0.25, 0.26]), [Record::new(values(0.25)), Record::new(values(0.26))]);
+    }
+
+    #[test]
+    fn binary_round_trip()
+    {
+        for format in [Format::Cobs, Format::Slip]
+        {
+            let settings = Settings { format, layout: "u32 t, i16x2, f64".to_string(), time_field: "t".to_string(), time_unit: TimeUnit::Ms, checksum: Checksum::Crc16, ..Settings::default() };
+            let mut simulator = Simulator::new(&settings).unwrap();
+            let mut decoder = Decoder::new(format.framing().unwrap(), &settings).unwrap();
+            decoder.sync();
+
+            for (time, timestamp) in [(0.25, 0.0), (0.26, 0.01)]
+            {
+                let values = simulator.values(time);
+                let frames = decoder.push(&simulator.encode(time, values));
+
+                // Integer fields are scaled to their range, the time field is taken out of the values.
+                let expected = sine(time, 4);
+                let values = vec![None, Some((expected[1] * 10_000.0).round()), Some((expected[2] * 10_000.0).round()), Some(expected[3])];
+                assert_eq!(frames.into_iter().map(|(_, record)| record).collect::<Vec<_>>(), [Some(Record { timestamp: Some(timestamp), values })]);
+            }
+        }
+    }
+}
@@ -0,0 +1,769 @@
+// Places the bytes come from: a serial port, a network connection, a pipe, a file or the simulated device.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+use crate::replay::ReplaySource;
+use crate::settings::Settings;
+use crate::simulator::Simulator;
+
+const TCP_CLIENT: &str = "tcp://";
+const TCP_SERVER: &str = "tcp-listen://";
+const UDP: &str = "udp://";
+const FILE: &str = "file://";
+const SIMULATED: &str = "sim://";
+const STDIN: &str = "stdin://";
+const PIPE: &str = "pipe://";
+// Milliseconds DTR and RTS are dropped for by a reset pulse.
+const RESET_PULSE: u64 = 100;
+// Milliseconds a read waits for bytes before it returns none, so commands are not held up.
+pub const READ_WAIT: u64 = 20;
+
+pub trait Source: Send
+{
+    // Returns the bytes received since the last call as soon as there are any, empty if none came for `READ_WAIT`.
+    fn read(&mut self) -> io::Result<Vec<u8>>;
+
+    // Returns the bytes received since the last call by sender, for sources that tell senders apart.
+    fn read_from(&mut self) -> io::Result<Vec<(String, Vec<u8>)>>
+    {
+        Ok(vec![("".to_string(), self.read()?)])
+    }
+
+    // True if every chunk from `read_from` is a whole datagram.
+    fn datagrams(&self) -> bool
+    {
+        false
+    }
+
+    // True if the data starts at a frame, so there is no incomplete frame to skip.
+    fn starts_at_frame(&self) -> bool
+    {
+        self.datagrams()
+    }
+
+    // Sends data to the device, for sources that can.
+    fn write(&mut self, _data: &[u8]) -> io::Result<()>
+    {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "The source can not send"))
+    }
+
+    // Resets the device, for sources that can.
+    fn pulse_reset(&mut self) -> io::Result<()>
+    {
+        Ok(())
+    }
+
+    // Settings the data of this source is read with.
+    fn settings(&self, settings: &Settings) -> Settings
+    {
+        settings.clone()
+    }
+}
+
+// Where to read from, written in the port setting as a port name or `tcp://host:port`, `tcp-listen://address:port`, `udp://address:port`
+// `file://path` to play back a file, `sim://` for the simulated device, `stdin://` for the standard input
+// or `pipe://path` for a named pipe or any other file read as it grows.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Target
+{
+    Serial(String),
+    TcpClient(String),
+    TcpServer(String),
+    Udp(String),
+    Replay(String),
+    Simulated,
+    Stdin,
+    Pipe(String),
+}
+
+impl Target
+{
+    pub fn parse(name: &str) -> Target
+    {
+        if let Some(address) = name.strip_prefix(TCP_CLIENT)
+        {
+            return Target::TcpClient(address.to_string());
+        }
+        if let Some(address) = name.strip_prefix(TCP_SERVER)
+        {
+            return Target::TcpServer(address.to_string());
+        }
+        if let Some(address) = name.strip_prefix(UDP)
+        {
+            return Target::Udp(address.to_string());
+        }
+        if let Some(path) = name.strip_prefix(FILE)
+        {
+            return Target::Replay(path.to_string());
+        }
+        if name.starts_with(SIMULATED)
+        {
+            return Target::Simulated;
+        }
+        if name.starts_with(STDIN)
+        {
+            return Target::Stdin;
+        }
+        if let Some(path) = name.strip_prefix(PIPE)
+        {
+            return Target::Pipe(path.to_string());
+        }
+        Target::Serial(name.to_string())
+    }
+
+    pub fn is_network(&self) -> bool
+    {
+        matches!(self, Target::TcpClient(_) | Target::TcpServer(_) | Target::Udp(_))
+    }
+
+    pub fn open(&self, settings: &Settings) -> Result<Box<dyn Source>, String>
+    {
+        match self {
+            Target::Serial(name) => Ok(Box::new(SerialSource::open(name, settings)?)),
+            Target::TcpClient(address) => Ok(Box::new(TcpSource::connect(address)?)),
+            Target::TcpServer(address) => Ok(Box::new(TcpServerSource::listen(address)?)),
+            Target::Udp(address) => Ok(Box::new(UdpSource::bind(address)?)),
+            Target::Replay(path) => Ok(Box::new(ReplaySource::open(path, settings)?)),
+            Target::Simulated => Ok(Box::new(Simulator::new(settings)?)),
+            Target::Stdin => Ok(Box::new(PipeSource::stdin())),
+            Target::Pipe(path) => {
+                // Opening a named pipe waits for a writer, so only a missing path fails here.
+                if !Path::new(path).exists()
+                {
+                    return Err("Failed to open ".to_string() + path);
+                }
+                // A regular file is followed as it grows, a named pipe ends when its writer closes it.
+                let follow = Path::new(path).is_file();
+                let path = path.to_string();
+                Ok(Box::new(PipeSource::spawn(move || File::open(path), follow)))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Parity
+{
+    None,
+    Odd,
+    Even,
+}
+
+impl Parity
+{
+    pub const ALL: [Parity; 3] = [Parity::None, Parity::Odd, Parity::Even];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Parity::None => "none",
+            Parity::Odd => "odd",
+            Parity::Even => "even",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Parity>
+    {
+        Parity::ALL.into_iter().find(|parity| parity.name() == name)
+    }
+
+    // Letter of the parity in a line setting like `8N1`.
+    pub fn letter(&self) -> &'static str
+    {
+        match self {
+            Parity::None => "N",
+            Parity::Odd => "O",
+            Parity::Even => "E",
+        }
+    }
+
+    fn port(&self) -> serialport::Parity
+    {
+        match self {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlowControl
+{
+    None,
+    // XON/XOFF.
+    Software,
+    // RTS/CTS.
+    Hardware,
+}
+
+impl FlowControl
+{
+    pub const ALL: [FlowControl; 3] = [FlowControl::None, FlowControl::Software, FlowControl::Hardware];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            FlowControl::None => "none",
+            FlowControl::Software => "xon/xoff",
+            FlowControl::Hardware => "rts/cts",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<FlowControl>
+    {
+        FlowControl::ALL.into_iter().find(|flow_control| flow_control.name() == name)
+    }
+
+    fn port(&self) -> serialport::FlowControl
+    {
+        match self {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Reconnect
+{
+    Off,
+    // Opens the same port name again.
+    Path,
+    // Finds the same USB device by its VID, PID and serial number, under any port name.
+    Usb,
+}
+
+impl Reconnect
+{
+    pub const ALL: [Reconnect; 3] = [Reconnect::Off, Reconnect::Path, Reconnect::Usb];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Reconnect::Off => "off",
+            Reconnect::Path => "path",
+            Reconnect::Usb => "usb",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Reconnect>
+    {
+        Reconnect::ALL.into_iter().find(|reconnect| reconnect.name() == name)
+    }
+}
+
+// USB device of a serial port, to find it again after it was unplugged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsbId
+{
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+}
+
+impl UsbId
+{
+    pub fn of_port(name: &str) -> Option<UsbId>
+    {
+        serialport::available_ports().unwrap_or_default().into_iter()
+            .find(|port| port.port_name == name)
+            .and_then(|port| match port.port_type {
+                serialport::SerialPortType::UsbPort(info) => Some(UsbId { vid: info.vid, pid: info.pid, serial_number: info.serial_number }),
+                _ => None,
+            })
+    }
+
+    // Name of the port the device has now, if it is plugged in.
+    pub fn find_port(&self) -> Option<String>
+    {
+        serialport::available_ports().unwrap_or_default().into_iter()
+            .find(|port| match &port.port_type {
+                serialport::SerialPortType::UsbPort(info) => info.vid == self.vid && info.pid == self.pid && info.serial_number == self.serial_number,
+                _ => false,
+            })
+            .map(|port| port.port_name)
+    }
+}
+
+pub struct SerialSource
+{
+    port: Box<dyn serialport::SerialPort>,
+    settings: Settings,
+}
+
+impl SerialSource
+{
+    pub fn open(name: &str, settings: &Settings) -> Result<SerialSource, String>
+    {
+        let (Ok(data_bits), Ok(stop_bits)) = (serialport::DataBits::try_from(settings.data_bits), serialport::StopBits::try_from(settings.stop_bits)) else
+        {
+            return Err("Incorrect line settings: ".to_string() + &settings.line());
+        };
+
+        let port = serialport::new(name, settings.speed)
+            .data_bits(data_bits)
+            .parity(settings.parity.port())
+            .stop_bits(stop_bits)
+            .flow_control(settings.flow_control.port())
+            .timeout(Duration::from_millis(READ_WAIT))
+            .open();
+
+        let Ok(mut port) = port else
+        {
+            return Err("Failed to open port".to_string());
+        };
+
+        let _ = port.write_data_terminal_ready(settings.dtr);
+        // With hardware flow control RTS belongs to the driver.
+        if settings.flow_control != FlowControl::Hardware
+        {
+            let _ = port.write_request_to_send(settings.rts);
+        }
+
+        // A board that resets on open prints its boot messages while it settles, they are dropped.
+        if settings.settle > 0.0
+        {
+            thread::sleep(Duration::from_secs_f64(settings.settle));
+            let _ = port.clear(serialport::ClearBuffer::Input);
+        }
+
+        Ok(SerialSource { port, settings: settings.clone() })
+    }
+}
+
+impl Source for SerialSource
+{
+    // Waits for the first byte, then takes all the port has buffered.
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        let mut serial_buf: Vec<u8> = vec![0; 1];
+        match self.port.read(serial_buf.as_mut_slice()) {
+            Ok(0) => return Ok(Vec::new()),
+            Ok(_) => (),
+            Err(error) if matches!(error.kind(), io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        }
+
+        let bytes = self.port.bytes_to_read()? as usize;
+        if bytes > 0
+        {
+            serial_buf.resize(1 + bytes, 0);
+            let n = self.port.read(&mut serial_buf[1..])?;
+            serial_buf.truncate(1 + n);
+        }
+        Ok(serial_buf)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()>
+    {
+        self.port.write_all(data)?;
+        self.port.flush()
+    }
+
+    // Turns DTR and RTS over for a moment, which resets boards like the Arduino.
+    fn pulse_reset(&mut self) -> io::Result<()>
+    {
+        for (step, (dtr, rts)) in reset_pulse(&self.settings).into_iter().enumerate()
+        {
+            if step > 0
+            {
+                thread::sleep(Duration::from_millis(RESET_PULSE));
+            }
+            self.port.write_data_terminal_ready(dtr)?;
+            if let Some(rts) = rts
+            {
+                self.port.write_request_to_send(rts)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Levels of DTR and RTS during a reset pulse and after it, RTS is left alone when it controls the flow.
+// The pulse goes to the opposite of the levels set on open, so it gives an edge whatever they are.
+fn reset_pulse(settings: &Settings) -> [(bool, Option<bool>); 2]
+{
+    let rts = settings.flow_control != FlowControl::Hardware;
+    [(!settings.dtr, rts.then_some(!settings.rts)), (settings.dtr, rts.then_some(settings.rts))]
+}
+
+// A connection that blocks reads for `READ_WAIT` at most.
+fn set_waiting(stream: &TcpStream)
+{
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(READ_WAIT)));
+}
+
+// Reads what the stream has buffered once there is something, an error if the other side closed it.
+fn read_available(stream: &mut TcpStream) -> io::Result<Vec<u8>>
+{
+    let mut buf = vec![0u8; 65_536];
+
+    match stream.read(&mut buf) {
+        Ok(0) => Err(io::Error::new(io::ErrorKind::ConnectionAborted, "Connection closed")),
+        Ok(n) => {
+            buf.truncate(n);
+            Ok(buf)
+        }
+        Err(error) if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut | io::ErrorKind::Interrupted) => Ok(Vec::new()),
+        Err(error) => Err(error),
+    }
+}
+
+pub struct TcpSource
+{
+    stream: TcpStream,
+}
+
+impl TcpSource
+{
+    pub fn connect(address: &str) -> Result<TcpSource, String>
+    {
+        let stream = TcpStream::connect(address).map_err(|_| "Failed to connect to ".to_string() + address)?;
+        set_waiting(&stream);
+        Ok(TcpSource { stream })
+    }
+}
+
+impl Source for TcpSource
+{
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        read_available(&mut self.stream)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()>
+    {
+        self.stream.write_all(data)
+    }
+}
+
+// Waits for one client at a time, and for the next one after it disconnects.
+pub struct TcpServerSource
+{
+    listener: TcpListener,
+    client: Option<TcpStream>,
+}
+
+impl TcpServerSource
+{
+    pub fn listen(address: &str) -> Result<TcpServerSource, String>
+    {
+        let listener = TcpListener::bind(address).map_err(|_| "Failed to listen on ".to_string() + address)?;
+        let _ = listener.set_nonblocking(true);
+        Ok(TcpServerSource { listener, client: None })
+    }
+}
+
+impl Source for TcpServerSource
+{
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        if self.client.is_none()
+        {
+            match self.listener.accept() {
+                Ok((client, _)) => {
+                    set_waiting(&client);
+                    self.client = Some(client);
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(READ_WAIT));
+                    return Ok(Vec::new());
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        let data = read_available(self.client.as_mut().unwrap());
+        if data.is_err()
+        {
+            self.client = None;
+            return Ok(Vec::new());
+        }
+        data
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()>
+    {
+        match &mut self.client {
+            Some(client) => client.write_all(data),
+            None => Err(io::Error::new(io::ErrorKind::NotConnected, "No client")),
+        }
+    }
+}
+
+// Receives datagrams from any number of senders.
+pub struct UdpSource
+{
+    socket: UdpSocket,
+}
+
+impl UdpSource
+{
+    pub fn bind(address: &str) -> Result<UdpSource, String>
+    {
+        let socket = UdpSocket::bind(address).map_err(|_| "Failed to listen on ".to_string() + address)?;
+        let _ = socket.set_read_timeout(Some(Duration::from_millis(READ_WAIT)));
+        Ok(UdpSource { socket })
+    }
+}
+
+impl Source for UdpSource
+{
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        Ok(self.read_from()?.into_iter().flat_map(|(_, data)| data).collect())
+    }
+
+    // Waits for the first datagram, then takes the ones that came with it without waiting.
+    fn read_from(&mut self) -> io::Result<Vec<(String, Vec<u8>)>>
+    {
+        let mut datagrams = Vec::new();
+        let mut buf = [0u8; 65_536];
+
+        loop
+        {
+            let _ = self.socket.set_nonblocking(!datagrams.is_empty());
+            match self.socket.recv_from(&mut buf) {
+                Ok((n, sender)) => datagrams.push((sender.to_string(), buf[..n].to_vec())),
+                Err(error) if matches!(error.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                // A datagram sent earlier was refused, which says nothing about the next ones.
+                Err(error) if error.kind() == io::ErrorKind::ConnectionReset => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        let _ = self.socket.set_nonblocking(false);
+        Ok(datagrams)
+    }
+
+    fn datagrams(&self) -> bool
+    {
+        true
+    }
+}
+
+// Reads of the thread, shared by the sources reading the standard input one after another.
+type Reads = Arc<Mutex<mpsc::Receiver<io::Result<Vec<u8>>>>>;
+
+// Reads a blocking stream on its own thread, so a pipe without a writer yet does not hold up the reading loop.
+pub struct PipeSource
+{
+    rx: Reads,
+    error: Option<io::Error>,
+}
+
+// The standard input is read by one thread for the whole process, so reading started again goes on where it stopped.
+static STDIN_READER: OnceLock<Reads> = OnceLock::new();
+
+impl PipeSource
+{
+    // With `follow` the end of the input is waited past for the bytes written after it.
+    pub fn spawn<R: Read, F: FnOnce() -> io::Result<R> + Send + 'static>(open: F, follow: bool) -> PipeSource
+    {
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let mut reader = match open() {
+                Ok(reader) => reader,
+                Err(error) => {
+                    let _ = tx.send(Err(error));
+                    return;
+                }
+            };
+
+            let mut buf = [0u8; 4096];
+            loop
+            {
+                let data = match reader.read(&mut buf) {
+                    // Waits for the file to grow, the empty send finds out whether the source was dropped.
+                    Ok(0) if follow => {
+                        thread::sleep(Duration::from_millis(READ_WAIT));
+                        Ok(Vec::new())
+                    }
+                    Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "End of input")),
+                    Ok(n) => Ok(buf[..n].to_vec()),
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(error) => Err(error),
+                };
+
+                let end = data.is_err();
+                if tx.send(data).is_err() || end
+                {
+                    return;
+                }
+            }
+        });
+
+        PipeSource { rx: Arc::new(Mutex::new(rx)), error: None }
+    }
+
+    pub fn stdin() -> PipeSource
+    {
+        let rx = STDIN_READER.get_or_init(|| PipeSource::spawn(|| Ok(io::stdin()), false).rx);
+        PipeSource { rx: rx.clone(), error: None }
+    }
+}
+
+impl Source for PipeSource
+{
+    // Waits for the first bytes, then takes the ones that came with them without waiting.
+    fn read(&mut self) -> io::Result<Vec<u8>>
+    {
+        let rx = self.rx.lock().unwrap();
+        let mut data = Vec::new();
+        let mut received = match self.error {
+            Some(_) => Err(mpsc::TryRecvError::Empty),
+            None => rx.recv_timeout(Duration::from_millis(READ_WAIT)).map_err(|error| match error {
+                mpsc::RecvTimeoutError::Timeout => mpsc::TryRecvError::Empty,
+                mpsc::RecvTimeoutError::Disconnected => mpsc::TryRecvError::Disconnected,
+            }),
+        };
+
+        loop
+        {
+            match received {
+                Ok(Ok(bytes)) => data.extend(bytes),
+                Ok(Err(error)) => {
+                    // The bytes read before the end are returned first.
+                    self.error = Some(error);
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if self.error.is_none()
+                    {
+                        self.error = Some(io::Error::new(io::ErrorKind::UnexpectedEof, "End of input"));
+                    }
+                    break;
+                }
+            }
+            received = rx.try_recv();
+        }
+
+        match self.error.take() {
+            Some(error) if data.is_empty() => Err(error),
+            error => {
+                self.error = error;
+                Ok(data)
+            }
+        }
+    }
+
+    // The writer of a pipe starts with it, so the first line is a whole one.
+    fn starts_at_frame(&self) -> bool
+    {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use std::time::Instant;
+
+    // Reads until `len` bytes came, or gives up after a second.
+    fn read_len(source: &mut dyn Source, len: usize) -> Vec<u8>
+    {
+        let start = Instant::now();
+        let mut data = Vec::new();
+        while data.len() < len && start.elapsed() < Duration::from_secs(1)
+        {
+            data.extend(source.read().unwrap());
+        }
+        data
+    }
+
+    #[test]
+    fn reset_levels()
+    {
+        let settings = Settings { dtr: true, rts: true, ..Settings::default() };
+        assert_eq!(reset_pulse(&settings), [(false, Some(false)), (true, Some(true))]);
+
+        // With both lines released on open the pulse asserts them.
+        let settings = Settings { dtr: false, rts: false, ..Settings::default() };
+        assert_eq!(reset_pulse(&settings), [(true, Some(true)), (false, Some(false))]);
+
+        let settings = Settings { dtr: false, rts: true, flow_control: FlowControl::Hardware, ..Settings::default() };
+        assert_eq!(reset_pulse(&settings), [(true, None), (false, None)]);
+    }
+
+    #[test]
+    fn tcp_client()
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut source = TcpSource::connect(&listener.local_addr().unwrap().to_string()).unwrap();
+        let (mut device, _) = listener.accept().unwrap();
+
+        // Nothing was sent yet, so the read gives up after a while.
+        assert_eq!(source.read().unwrap(), b"");
+
+        device.write_all(b"1,2\n").unwrap();
+        device.write_all(b"3,4\n").unwrap();
+        assert_eq!(read_len(&mut source, 8), b"1,2\n3,4\n");
+
+        source.write(b"reset\n").unwrap();
+        let mut buf = [0u8; 6];
+        device.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"reset\n");
+
+        drop(device);
+        assert_eq!(source.read().unwrap_err().kind(), io::ErrorKind::ConnectionAborted);
+    }
+
+    #[test]
+    fn tcp_server()
+    {
+        let mut source = TcpServerSource::listen("127.0.0.1:0").unwrap();
+        let address = source.listener.local_addr().unwrap();
+        assert_eq!(source.read().unwrap(), b"");
+        assert_eq!(source.write(b"x").unwrap_err().kind(), io::ErrorKind::NotConnected);
+
+        // The next client is taken once the first one disconnects.
+        for text in [&b"1,2\n"[..], b"3,4\n"]
+        {
+            let mut device = TcpStream::connect(address).unwrap();
+            device.write_all(text).unwrap();
+            assert_eq!(read_len(&mut source, text.len()), text);
+
+            source.write(b"ok").unwrap();
+            let mut buf = [0u8; 2];
+            device.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"ok");
+
+            drop(device);
+            let start = Instant::now();
+            while source.client.is_some() && start.elapsed() < Duration::from_secs(1)
+            {
+                assert_eq!(source.read().unwrap(), b"");
+            }
+            assert!(source.client.is_none());
+        }
+    }
+
+    #[test]
+    fn growing_file()
+    {
+        // Named after the process, so test runs at the same time do not share the file.
+        let path = std::env::temp_dir().join("serial-plotter-growing-file-".to_string() + &std::process::id().to_string() + ".txt");
+        std::fs::write(&path, "1,2\n").unwrap();
+
+        let mut source = Target::Pipe(path.to_string_lossy().to_string()).open(&Settings::default()).unwrap();
+        assert_eq!(read_len(source.as_mut(), 4), b"1,2\n");
+
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"3,4\n").unwrap();
+        assert_eq!(read_len(source.as_mut(), 4), b"3,4\n");
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
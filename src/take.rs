@@ -0,0 +1,116 @@
+// How the frames received during one update interval are taken for the display.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Take
+{
+    // Every frame as it is.
+    All,
+    // The last value received.
+    Lost,
+    Mean,
+    Min,
+    Max,
+    // The mean, with a band from the lowest to the highest value.
+    Envelope,
+    Median,
+    Rms,
+    // Standard deviation.
+    Std,
+}
+
+impl Take
+{
+    pub const ALL: [Take; 9] = [Take::All, Take::Lost, Take::Mean, Take::Min, Take::Max, Take::Envelope, Take::Median, Take::Rms, Take::Std];
+
+    pub fn name(&self) -> &'static str
+    {
+        match self {
+            Take::All => "all",
+            Take::Lost => "lost",
+            Take::Mean => "mean",
+            Take::Min => "min",
+            Take::Max => "max",
+            Take::Envelope => "envelope",
+            Take::Median => "median",
+            Take::Rms => "rms",
+            Take::Std => "std",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Take>
+    {
+        Take::ALL.into_iter().find(|take| take.name() == name)
+    }
+
+    // Value of a channel over the interval, from the values it received in order.
+    pub fn value(&self, values: &[f64]) -> Option<f64>
+    {
+        let last = *values.last()?;
+        let len = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / len;
+
+        let value = match self {
+            Take::All | Take::Lost => last,
+            Take::Mean | Take::Envelope => mean,
+            Take::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            Take::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Take::Median => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let middle = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] }
+            }
+            Take::Rms => (values.iter().map(|value| value * value).sum::<f64>() / len).sqrt(),
+            Take::Std => (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / len).sqrt(),
+        };
+        Some(value)
+    }
+
+    // Lowest and highest value, drawn around the line of an envelope.
+    pub fn band(&self, values: &[f64]) -> Option<[f64; 2]>
+    {
+        if *self != Take::Envelope || values.is_empty()
+        {
+            return None;
+        }
+        Some([Take::Min.value(values)?, Take::Max.value(values)?])
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn value()
+    {
+        let values = [1.0, 2.0, 3.0, 10.0];
+        assert_eq!(Take::All.value(&values), Some(10.0));
+        assert_eq!(Take::Lost.value(&values), Some(10.0));
+        assert_eq!(Take::Mean.value(&values), Some(4.0));
+        assert_eq!(Take::Envelope.value(&values), Some(4.0));
+        assert_eq!(Take::Min.value(&values), Some(1.0));
+        assert_eq!(Take::Max.value(&values), Some(10.0));
+        assert_eq!(Take::Median.value(&values), Some(2.5));
+        assert_eq!(Take::Rms.value(&values), Some(28.5f64.sqrt()));
+        assert_eq!(Take::Std.value(&values), Some(12.5f64.sqrt()));
+
+        // The values come in order of arrival, the median sorts them.
+        assert_eq!(Take::Median.value(&[3.0, -1.0, 2.0]), Some(2.0));
+        assert_eq!(Take::Rms.value(&[-3.0, 4.0]), Some(12.5f64.sqrt()));
+
+        for take in Take::ALL
+        {
+            assert_eq!(take.value(&[]), None);
+        }
+    }
+
+    #[test]
+    fn band()
+    {
+        assert_eq!(Take::Envelope.band(&[1.0, 2.0, 3.0, 10.0]), Some([1.0, 10.0]));
+        assert_eq!(Take::Envelope.band(&[]), None);
+        assert_eq!(Take::Mean.band(&[1.0, 2.0]), None);
+    }
+}